@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+use sea_orm_migration::seaql_migrations::Relation;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(DeriveEntityModel)]
+#[sea_orm(table_name = "knowledge_config_history")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub file_name: String,
+    /// 同一配置内从 1 开始递增的修订号
+    pub revision: i32,
+    /// 产生该修订的操作：create / update / active / upload / rollback / delete
+    pub action: String,
+    pub author: Option<String>,
+    pub config_content: Option<String>,
+    pub create_sql: Option<String>,
+    pub insert_sql: Option<String>,
+    pub data_content: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTimeUtc,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
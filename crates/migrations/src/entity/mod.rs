@@ -1,4 +1,6 @@
 // 数据库实体定义
 pub mod knowledge_config;
+pub mod knowledge_config_history;
 
 pub use knowledge_config::Entity as KnowledgeConfig;
+pub use knowledge_config_history::Entity as KnowledgeConfigHistory;
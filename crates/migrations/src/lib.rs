@@ -3,7 +3,11 @@
 pub use sea_orm_migration::prelude::*;
 
 pub mod entity;
+mod m20241231_000001_detach_legacy_table;
 mod m20250101_000001_create_tables;
+mod m20250301_000001_import_legacy_configs;
+mod m20250601_000002_create_history;
+mod m20250701_000003_unique_file_name;
 
 pub use entity::*;
 
@@ -13,7 +17,11 @@ pub struct Migrator;
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
+            Box::new(m20241231_000001_detach_legacy_table::Migration),
             Box::new(m20250101_000001_create_tables::Migration),
+            Box::new(m20250301_000001_import_legacy_configs::Migration),
+            Box::new(m20250601_000002_create_history::Migration),
+            Box::new(m20250701_000003_unique_file_name::Migration),
         ]
     }
 }
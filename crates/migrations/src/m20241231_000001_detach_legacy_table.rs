@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+use sea_orm::DbBackend;
+
+/// 旧版 SQL 脚本创建的 `knowledge_configs` 改名后的表名，数据由
+/// `m20250301_000001_import_legacy_configs` 导入新表后删除
+pub(crate) const LEGACY_TABLE: &str = "knowledge_configs_legacy";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 旧版 SQL 脚本只支持 PostgreSQL，`status` 列只存在于脚本创建的表中
+        if manager.get_database_backend() != DbBackend::Postgres
+            || !manager.has_column("knowledge_configs", "status").await?
+        {
+            return Ok(());
+        }
+
+        // 让出表名，由 m20250101_000001_create_tables 按实体重新建表
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(Alias::new("knowledge_configs"), Alias::new(LEGACY_TABLE))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // 旧表在导入后即被删除，无需恢复
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use sea_orm_migration::prelude::*;
-use sea_orm::{Schema, DbBackend};
+use sea_orm::Schema;
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
@@ -7,7 +7,7 @@ pub struct Migration;
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        let schema = Schema::new(DbBackend::Postgres);
+        let schema = Schema::new(manager.get_database_backend());
 
         // 创建 knowledge_configs 表
         let stmt = schema.create_table_from_entity(crate::entity::knowledge_config::Entity);
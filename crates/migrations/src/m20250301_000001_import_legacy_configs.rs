@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20241231_000001_detach_legacy_table::LEGACY_TABLE;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 只有由旧版 SQL 脚本建表的库才存在旧表
+        if !manager.has_table(LEGACY_TABLE).await? {
+            return Ok(());
+        }
+
+        // `status` 转换为 `is_active`；旧版时间列为不带时区的 TIMESTAMP，按 UTC 解释
+        manager
+            .get_connection()
+            .execute_unprepared(&format!(
+                r#"
+                INSERT INTO knowledge_configs
+                    (file_name, config_content, create_sql, insert_sql, data_content,
+                     is_active, updated_at, created_at)
+                SELECT file_name, config_content, create_sql, insert_sql, data_content,
+                       COALESCE(status, 'active') = 'active',
+                       COALESCE(update_time, created_at, CURRENT_TIMESTAMP AT TIME ZONE 'UTC')
+                           AT TIME ZONE 'UTC',
+                       COALESCE(created_at, CURRENT_TIMESTAMP AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                FROM {LEGACY_TABLE}
+                ORDER BY id
+                "#
+            ))
+            .await?;
+
+        // tag_name、owner、created_by 等废弃列不再保留
+        manager
+            .drop_table(Table::drop().table(Alias::new(LEGACY_TABLE)).to_owned())
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // 旧表结构已废弃，导入不可逆
+        Ok(())
+    }
+}
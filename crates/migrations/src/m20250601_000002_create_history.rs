@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+use sea_orm::Schema;
+
+use crate::entity::knowledge_config_history::{Column, Entity};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let schema = Schema::new(manager.get_database_backend());
+
+        // 创建 knowledge_config_history 表，记录知识库配置的每次修订
+        let stmt = schema.create_table_from_entity(Entity);
+        manager.create_table(stmt).await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_knowledge_config_history_file_revision")
+                    .table(Entity)
+                    .col(Column::FileName)
+                    .col(Column::Revision)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Entity).to_owned()).await?;
+        Ok(())
+    }
+}
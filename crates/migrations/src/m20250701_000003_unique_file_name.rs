@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+use crate::entity::knowledge_config::{Column, Entity};
+
+const INDEX_NAME: &str = "idx_knowledge_configs_file_name";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 已有重复名称时给出可读的错误，需先人工清理
+        let duplicates = Query::select()
+            .column(Column::FileName)
+            .from(Entity)
+            .group_by_col(Column::FileName)
+            .and_having(Expr::col(Column::Id).count().gt(1))
+            .to_owned();
+        let db = manager.get_connection();
+        let rows = db
+            .query_all(manager.get_database_backend().build(&duplicates))
+            .await?;
+        if !rows.is_empty() {
+            let names = rows
+                .iter()
+                .map(|row| row.try_get::<String>("", "file_name"))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Err(DbErr::Migration(format!(
+                "knowledge_configs 存在重复的 file_name，请清理后重新执行迁移: {}",
+                names.join(", ")
+            )));
+        }
+
+        // 知识库名称唯一，新建配置的并发检查由该索引兜底
+        manager
+            .create_index(
+                Index::create()
+                    .name(INDEX_NAME)
+                    .table(Entity)
+                    .col(Column::FileName)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name(INDEX_NAME).table(Entity).to_owned())
+            .await
+    }
+}
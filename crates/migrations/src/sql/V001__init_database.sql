@@ -1,6 +1,7 @@
 -- WpEditor 数据库初始化脚本
 -- 版本: V001
 -- 描述: 创建基础表结构
+-- 已废弃: 表结构以 SeaORM 迁移（crates/migrations）为准，服务启动时会自动升级由本脚本创建的表
 
 -- ============================================
 -- 1. 知识库配置表
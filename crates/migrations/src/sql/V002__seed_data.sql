@@ -1,6 +1,8 @@
 -- WpEditor 初始化数据脚本
 -- 版本: V002
 -- 描述: 插入初始化数据
+-- 已废弃: 本脚本写入 V001 旧表的 tag_name、owner、status、created_by 列，在 SeaORM 迁移创建的表上执行会失败；
+--         示例知识库请通过 POST /api/knowledge/configs 创建
 
 -- ============================================
 -- 1. 插入示例知识库配置
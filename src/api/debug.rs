@@ -1,11 +1,18 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // 模拟调试 API
 use crate::error::AppError;
-use crate::server::examples;
-use crate::utils::{convert_record, record_to_fields, warp_check_record};
-use crate::{OmlFormatter, ParsedField, Setting, WplFormatter};
+use crate::server::{cases, examples};
+use crate::utils::format_options::{FormatCheck, FormatOptions, FormatOverrides};
+use crate::utils::knowledge;
+use crate::utils::perf::{BenchConfig, BenchReport, run_benchmark};
+use crate::utils::sql_guard::{self, QueryLimits};
+use crate::utils::{
+    AnnotationDiff, ParseOptions, ParseOutcome, convert_record, record_spans, record_to_fields,
+    warp_check_batch, warp_check_outcome, warp_rule_matrix, warp_trace,
+};
+use crate::{FieldSpan, OmlFormatter, ParsedField, Setting, WplFormatter};
 use actix_web::{HttpResponse, get, post, web};
 use base64::Engine;
 use base64::engine::general_purpose;
@@ -15,25 +22,126 @@ use wp_model_core::model::data::Record;
 use wp_model_core::model::fmt_def::TextFmt;
 use wp_model_core::model::{DataField, DataRecord};
 
+/// 调试解析模式
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseMode {
+    /// 整段日志视为一条事件
+    #[default]
+    Single,
+    /// 按分隔符切分后逐条解析
+    Batch,
+    /// 对同一输入运行全部规则，输出规则匹配矩阵
+    Matrix,
+    /// 规则级匹配跟踪：按规则顺序输出每条规则的匹配结果（成功/回退/失败）及命中规则的字段。
+    ///
+    /// 不是分组/字段/管道粒度的逐步回放：引擎不暴露规则内部的回溯过程，
+    /// 字段位置为反查得到的近似值
+    Trace,
+}
+
 #[derive(Deserialize)]
 pub struct DebugParseRequest {
     pub connection_id: Option<i32>,
     pub rules: String,
     pub logs: String,
+    #[serde(default)]
+    pub mode: ParseMode,
+    /// 批量模式下的事件分隔符，缺省按行切分
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// 输出选项：事件 ID 策略、是否保留忽略字段、是否追加标签
+    #[serde(default)]
+    pub options: ParseOptions,
+}
+
+#[derive(Serialize)]
+pub struct BatchItemResponse {
+    pub index: usize,
+    pub line: usize,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<DataRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format_json: Option<String>,
+    /// 字段在该条事件文本中的近似位置
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<FieldSpan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchParseResponse {
+    pub total: usize,
+    pub success: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemResponse>,
 }
 
 // 新版调试接口：解析日志并返回字段列表
 #[post("/api/debug/parse")]
 pub async fn debug_parse(req: web::Json<DebugParseRequest>) -> Result<HttpResponse, AppError> {
-    // 调用 warp_check_record 获取 DataRecord
-    let record = warp_check_record(&req.rules, &req.logs)?;
+    match req.mode {
+        ParseMode::Batch => return debug_parse_batch(&req),
+        ParseMode::Matrix => {
+            let matrix = warp_rule_matrix(&req.rules, &req.logs)?;
+            return Ok(HttpResponse::Ok().json(matrix));
+        }
+        ParseMode::Trace => {
+            // 仅到规则粒度，字段位置为近似值，见 warp_trace
+            let trace = warp_trace(&req.rules, &req.logs)?;
+            return Ok(HttpResponse::Ok().json(trace));
+        }
+        ParseMode::Single => {}
+    }
+
+    // 调用 warp_check_outcome 获取 DataRecord 及注解执行情况
+    let ParseOutcome {
+        record, annotation, ..
+    } = warp_check_outcome(&req.rules, &req.logs, &req.options)?;
 
     // 直接返回 DataField 列表，由 Actix 负责序列化为 JSON
     let formatter = FormatType::from(&TextFmt::Json);
     let json_string = formatter.format_record(&record);
+    let spans = record_spans(&req.logs, &record);
     Ok(HttpResponse::Ok().json(RecordResponseRaw {
         fields: record,
         format_json: json_string,
+        spans,
+        annotation,
+    }))
+}
+
+// 批量解析：逐条返回解析结果与成功/失败汇总
+fn debug_parse_batch(req: &DebugParseRequest) -> Result<HttpResponse, AppError> {
+    let result = warp_check_batch(
+        &req.rules,
+        &req.logs,
+        req.delimiter.as_deref(),
+        &req.options,
+    )?;
+
+    let formatter = FormatType::from(&TextFmt::Json);
+    let items = result
+        .items
+        .into_iter()
+        .map(|item| BatchItemResponse {
+            index: item.index,
+            line: item.line,
+            success: item.success,
+            format_json: item.record.as_ref().map(|r| formatter.format_record(r)),
+            fields: item.record,
+            spans: item.spans,
+            error: item.error,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(BatchParseResponse {
+        total: result.total,
+        success: result.success,
+        failed: result.failed,
+        items,
     }))
 }
 
@@ -65,6 +173,12 @@ pub struct RecordResponseWithArray {
 pub struct RecordResponseRaw {
     pub fields: DataRecord,
     pub format_json: String,
+    /// 字段在原始日志中的近似位置，供编辑器悬停时高亮；无法可靠定位的字段不返回
+    #[serde(default)]
+    pub spans: Vec<FieldSpan>,
+    /// 命中规则的注解执行前后字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<AnnotationDiff>,
 }
 
 // 新版调试接口：基于解析结果和 OML 进行转换
@@ -92,6 +206,47 @@ pub async fn debug_transform(
     }))
 }
 
+#[derive(Deserialize)]
+pub struct PerformanceRunRequest {
+    /// 测试类型：`parse` 仅执行 WPL，其余类型在配置了 OML 时一并执行转换
+    #[serde(default)]
+    pub test_type: Option<String>,
+    pub config: BenchConfig,
+}
+
+#[derive(Serialize)]
+pub struct PerformanceRunResponse {
+    pub status: &'static str,
+    pub test_type: String,
+    #[serde(flatten)]
+    pub report: BenchReport,
+}
+
+// 性能测试：同步执行基准测试并返回吞吐、延迟与分配统计
+#[post("/api/debug/performance/run")]
+pub async fn performance_run(
+    req: web::Json<PerformanceRunRequest>,
+) -> Result<HttpResponse, AppError> {
+    let PerformanceRunRequest {
+        test_type,
+        mut config,
+    } = req.into_inner();
+    let test_type = test_type.unwrap_or_else(|| "parse".to_string());
+    if test_type == "parse" {
+        config.oml = None;
+    }
+
+    let report = web::block(move || run_benchmark(&config))
+        .await
+        .map_err(AppError::internal)??;
+
+    Ok(HttpResponse::Ok().json(PerformanceRunResponse {
+        status: "completed",
+        test_type,
+        report,
+    }))
+}
+
 // 知识库调试
 #[derive(Deserialize)]
 pub struct DebugKnowledgeStatusQuery {
@@ -107,8 +262,17 @@ pub struct DebugKnowledgeStatusItem {
 #[derive(Deserialize)]
 pub struct DebugKnowledgeQueryRequest {
     pub connection_id: i32,
-    pub table: String,
+    /// 未提供 `sql` 时必填；与 `sql` 同时给出时 SQL 必须引用该表
+    #[serde(default)]
+    pub table: Option<String>,
+    /// 为空时查询 `table` 整张表
+    #[serde(default)]
     pub sql: String,
+    /// 页码，从 1 开始
+    #[serde(default)]
+    pub page: usize,
+    #[serde(default)]
+    pub page_size: usize,
 }
 
 #[derive(Serialize)]
@@ -117,6 +281,8 @@ pub struct DebugKnowledgeQueryResponse {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<String>>,
     pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
 }
 
 // 执行知识库 SQL 查询（调试用）
@@ -124,10 +290,22 @@ pub struct DebugKnowledgeQueryResponse {
 pub async fn debug_knowledge_query(
     req: web::Json<DebugKnowledgeQueryRequest>,
 ) -> Result<HttpResponse, AppError> {
-    // 暂时直接复用旧的 sql_query，未来可以根据 connection_id 切换数据库
-    let _sql = req.sql.clone();
+    // 知识库目前只有一份已加载实例，connection_id 预留给后续按连接切换
+    let req = req.into_inner();
+    let timeout = QueryLimits::from_setting().timeout;
+    let result = sql_guard::run_with_timeout(timeout, move || {
+        knowledge::query_page(req.table.as_deref(), &req.sql, req.page, req.page_size)
+    })
+    .await?;
 
-    todo!()
+    Ok(HttpResponse::Ok().json(DebugKnowledgeQueryResponse {
+        success: true,
+        columns: result.columns,
+        rows: result.rows,
+        total: result.total,
+        page: result.page,
+        page_size: result.page_size,
+    }))
 }
 
 #[get("/api/debug/examples")]
@@ -168,18 +346,101 @@ pub async fn debug_examples() -> HttpResponse {
     }
 }
 
+// 运行规则仓库中 cases.toml 定义的回归用例
+#[post("/api/debug/cases/run")]
+pub async fn debug_cases_run() -> Result<HttpResponse, AppError> {
+    let setting = Setting::load();
+    let result = web::block(move || {
+        cases::run_cases(
+            PathBuf::from(&setting.repo.wpl_rule_repo),
+            PathBuf::from(&setting.repo.oml_rule_repo),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(AppError::internal)?;
+
+    match result {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "error": {
+                "code": "CASE_RUN_ERROR",
+                "message": "运行规则用例失败",
+                "detail": e
+            }
+        }))),
+    }
+}
+
+/// 格式检查结果，附带格式化后的完整内容
+#[derive(Serialize)]
+struct FormatCheckResponse {
+    success: bool,
+    #[serde(flatten)]
+    check: FormatCheck,
+    content: String,
+}
+
+/// 格式化风格以规则仓库的 `.wpfmt.toml` 为基础，查询参数可逐项覆盖
+fn format_options(repo: &str, overrides: &FormatOverrides) -> Result<FormatOptions, AppError> {
+    FormatOptions::load(Path::new(repo)).map(|options| overrides.apply(options))
+}
+
+fn format_wpl(content: &str, overrides: &FormatOverrides) -> Result<String, AppError> {
+    let options = format_options(&Setting::load().repo.wpl_rule_repo, overrides)?;
+    WplFormatter::with_options(options).format(content)
+}
+
+fn format_oml(content: &str, overrides: &FormatOverrides) -> Result<String, AppError> {
+    let options = format_options(&Setting::load().repo.oml_rule_repo, overrides)?;
+    OmlFormatter::with_options(options).format(content)
+}
+
+fn format_check_response(original: &str, formatted: String, label: &str) -> HttpResponse {
+    HttpResponse::Ok().json(FormatCheckResponse {
+        success: true,
+        check: FormatCheck::new(original, &formatted, label),
+        content: formatted,
+    })
+}
+
 #[post("/api/debug/wpl/format")]
-pub async fn wpl_format(req: String) -> HttpResponse {
-    let formatter = WplFormatter::new();
-    let formatted = formatter.format_content(&req);
-    HttpResponse::Ok().json(formatted)
+pub async fn wpl_format(
+    req: String,
+    query: web::Query<FormatOverrides>,
+) -> Result<HttpResponse, AppError> {
+    let formatted = format_wpl(&req, &query)?;
+    Ok(HttpResponse::Ok().json(formatted))
 }
 
 #[post("/api/debug/oml/format")]
-pub async fn oml_format(req: String) -> HttpResponse {
-    let formatter = OmlFormatter::new();
-    let formatted = formatter.format_content(&req);
-    HttpResponse::Ok().json(formatted)
+pub async fn oml_format(
+    req: String,
+    query: web::Query<FormatOverrides>,
+) -> Result<HttpResponse, AppError> {
+    let formatted = format_oml(&req, &query)?;
+    Ok(HttpResponse::Ok().json(formatted))
+}
+
+/// 检查 WPL 是否已格式化并返回差异，不改写内容
+#[post("/api/debug/wpl/format/check")]
+pub async fn wpl_format_check(
+    req: String,
+    query: web::Query<FormatOverrides>,
+) -> Result<HttpResponse, AppError> {
+    let formatted = format_wpl(&req, &query)?;
+    Ok(format_check_response(&req, formatted, "rule.wpl"))
+}
+
+/// 检查 OML 是否已格式化并返回差异，不改写内容
+#[post("/api/debug/oml/format/check")]
+pub async fn oml_format_check(
+    req: String,
+    query: web::Query<FormatOverrides>,
+) -> Result<HttpResponse, AppError> {
+    let formatted = format_oml(&req, &query)?;
+    Ok(format_check_response(&req, formatted, "model.oml"))
 }
 
 #[post("/api/debug/decode/base64")]
@@ -1,13 +1,35 @@
 // 知识库 API
-use crate::db::{knowledge_config::KnowledgeConfigRepo, pool::DbPool};
-use crate::error::AppError;
-use actix_web::{HttpResponse, get, post, web};
+use crate::db::knowledge_config::{KnowledgeConfigRepo, NewKnowledgeConfig};
+use crate::db::knowledge_history::{KnowledgeConfigRevision, KnowledgeHistoryRepo};
+use crate::db::pool::DbPool;
+use crate::error::{AppError, DbError};
+use crate::server::Setting;
+use crate::server::knowledge::reload_knowledge;
+use crate::utils::csv_import::{self, DEFAULT_PREVIEW_ROWS, MAX_UPLOAD_BYTES};
+use crate::utils::diff::unified_diff;
+use crate::utils::knowledge::{self, DEFAULT_SAMPLE_ROWS, MAX_SAMPLE_ROWS, query_rows};
+use crate::utils::sql_guard::{self, QueryLimits, QueryResult};
+use actix_multipart::Multipart;
+use actix_web::{HttpRequest, HttpResponse, delete, get, post, put, web};
+use futures_util::StreamExt;
+use sea_orm::DatabaseTransaction;
 use serde::{Deserialize, Serialize};
-use wp_knowledge::facade::query as query_all;
+use std::path::PathBuf;
+
+/// 记录修订作者的请求头。
+///
+/// 服务未接入认证，作者由客户端自行填写、可被伪造，仅作追溯参考，不能用于审计
+const AUTHOR_HEADER: &str = "X-Author";
+
+/// 获取数据库连接池；未配置 `[database]` 时返回 `DatabaseUnavailable`
+pub(crate) fn require_pool(pool: Option<web::Data<DbPool>>) -> Result<web::Data<DbPool>, AppError> {
+    pool.ok_or(AppError::DatabaseUnavailable)
+}
 
 // 查询知识库（数据库）列表：从 knowledge_configs 表读取所有配置
 #[get("/api/db_list")]
-pub async fn get_db_list(pool: web::Data<DbPool>) -> Result<HttpResponse, AppError> {
+pub async fn get_db_list(pool: Option<web::Data<DbPool>>) -> Result<HttpResponse, AppError> {
+    let pool = require_pool(pool)?;
     let db = pool.inner();
     let repo = KnowledgeConfigRepo::new(db);
 
@@ -23,20 +45,587 @@ pub struct KnowdbQuery {
     pub sql: String,
 }
 
-// 执行知识库 SQL 查询（旧实现，返回自定义 JSON 格式）
+#[derive(Serialize)]
+pub struct KnowdbQueryResponse {
+    pub success: bool,
+    #[serde(flatten)]
+    pub result: QueryResult,
+}
+
+// 执行知识库 SQL 查询：仅允许单条 SELECT/EXPLAIN，受超时与行数上限约束
 #[post("/api/db")]
 pub async fn query(req: web::Json<KnowdbQuery>) -> Result<HttpResponse, AppError> {
     let req = req.into_inner();
 
-    let response = match query_all(&req.sql) {
-        Ok(result) => HttpResponse::Ok().json(&result),
-        Err(err) => {
-            error!("查询知识库失败: {}", err);
-            HttpResponse::BadRequest()
-                .content_type("application/json")
-                .body(err.to_string())
+    let result = sql_guard::run_read_only(&req.sql, QueryLimits::from_setting(), query_rows)
+        .await
+        .inspect_err(|err| warn!("查询知识库失败: {}", err))?;
+    if result.truncated {
+        info!("知识库查询结果超过 {} 行，已截断", result.row_count);
+    }
+
+    Ok(HttpResponse::Ok().json(KnowdbQueryResponse {
+        success: true,
+        result,
+    }))
+}
+
+/// 知识库配置内容：配置 TOML、建表 SQL、插入 SQL 与 CSV 数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnowledgeConfigContent {
+    #[serde(default)]
+    pub config_content: Option<String>,
+    #[serde(default)]
+    pub create_sql: Option<String>,
+    #[serde(default)]
+    pub insert_sql: Option<String>,
+    #[serde(default)]
+    pub data_content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateKnowledgeConfigRequest {
+    pub file_name: String,
+    #[serde(flatten)]
+    pub content: KnowledgeConfigContent,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateKnowledgeActiveRequest {
+    pub is_active: bool,
+}
+
+impl KnowledgeConfigContent {
+    fn into_new_config(self, file_name: String) -> NewKnowledgeConfig {
+        NewKnowledgeConfig {
+            file_name,
+            config_content: self.config_content,
+            create_sql: self.create_sql,
+            insert_sql: self.insert_sql,
+            data_content: self.data_content,
+        }
+    }
+}
+
+/// 修订作者，取自请求头 `X-Author`（客户端自报，未经认证）
+fn request_author(http_req: &HttpRequest) -> Option<String> {
+    http_req
+        .headers()
+        .get(AUTHOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 在变更所在事务中锁定配置行并记录修订，保证修订号分配与变更一同提交
+async fn record_revision(
+    txn: &DatabaseTransaction,
+    file_name: &str,
+    action: &str,
+    author: Option<String>,
+) -> Result<i32, AppError> {
+    let config = KnowledgeConfigRepo::new(txn)
+        .lock(file_name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("知识库配置 {file_name}")))?;
+    let revision = KnowledgeHistoryRepo::new(txn)
+        .record(&config, action, author)
+        .await?;
+    Ok(revision)
+}
+
+/// 提交变更事务
+async fn commit(txn: DatabaseTransaction) -> Result<(), AppError> {
+    txn.commit().await.map_err(DbError::from)?;
+    Ok(())
+}
+
+/// 变更提交后重新加载知识库；变更已落库，加载失败只记录日志
+async fn reload_after_change(pool: &DbPool) {
+    if let Err(e) = reload_knowledge(pool).await {
+        warn!("知识库配置已更新，但重新加载知识库失败: {}", e);
+    }
+}
+
+/// 校验知识库名称：作为目录与表名使用，只允许字母、数字、下划线与连字符。
+/// 所有按名称访问配置的接口都先校验，再访问数据库
+fn validate_file_name(file_name: &str) -> Result<(), AppError> {
+    if file_name.is_empty() || file_name.len() > 128 {
+        return Err(AppError::validation("知识库名称长度需在 1 到 128 之间"));
+    }
+    if !file_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(AppError::validation(format!(
+            "知识库名称只能包含字母、数字、下划线与连字符: {file_name}"
+        )));
+    }
+    Ok(())
+}
+
+/// 校验配置内容：TOML 可解析，建表 SQL 必填，提供数据时必须同时提供插入 SQL
+fn validate_content(content: &KnowledgeConfigContent) -> Result<(), AppError> {
+    let non_empty = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.trim().is_empty());
+
+    if let Some(config) = content.config_content.as_deref()
+        && let Err(e) = config.parse::<toml::Table>()
+    {
+        return Err(AppError::validation(format!("配置 TOML 格式错误: {e}")));
+    }
+    match content.create_sql.as_deref() {
+        Some(sql) if sql.to_ascii_uppercase().contains("CREATE TABLE") => {}
+        Some(sql) if !sql.trim().is_empty() => {
+            return Err(AppError::validation("建表 SQL 必须包含 CREATE TABLE 语句"));
+        }
+        _ => return Err(AppError::validation("建表 SQL 不能为空")),
+    }
+    if non_empty(&content.data_content) && !non_empty(&content.insert_sql) {
+        return Err(AppError::validation("提供 CSV 数据时插入 SQL 不能为空"));
+    }
+    Ok(())
+}
+
+// 查询所有知识库配置（含内容）
+#[get("/api/knowledge/configs")]
+pub async fn list_configs(pool: Option<web::Data<DbPool>>) -> Result<HttpResponse, AppError> {
+    let pool = require_pool(pool)?;
+    let configs = KnowledgeConfigRepo::new(pool.inner()).find_all().await?;
+    Ok(HttpResponse::Ok().json(configs))
+}
+
+// 查询单个知识库配置
+#[get("/api/knowledge/configs/{file_name}")]
+pub async fn get_config(
+    pool: Option<web::Data<DbPool>>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let config = KnowledgeConfigRepo::new(pool.inner())
+        .find_by_file_name(&file_name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("知识库配置 {file_name}")))?;
+    Ok(HttpResponse::Ok().json(config))
+}
+
+// 新建知识库配置，名称重复时返回校验错误
+#[post("/api/knowledge/configs")]
+pub async fn create_config(
+    pool: Option<web::Data<DbPool>>,
+    http_req: HttpRequest,
+    req: web::Json<CreateKnowledgeConfigRequest>,
+) -> Result<HttpResponse, AppError> {
+    let req = req.into_inner();
+    validate_file_name(&req.file_name)?;
+    validate_content(&req.content)?;
+
+    let pool = require_pool(pool)?;
+    let txn = pool.begin().await?;
+    let repo = KnowledgeConfigRepo::new(&txn);
+    if repo.find_by_file_name(&req.file_name).await?.is_some() {
+        return Err(AppError::validation(format!(
+            "知识库配置 {} 已存在",
+            req.file_name
+        )));
+    }
+
+    let id = repo
+        .create(req.content.into_new_config(req.file_name.clone()))
+        .await?;
+    let revision =
+        record_revision(&txn, &req.file_name, "create", request_author(&http_req)).await?;
+    commit(txn).await?;
+    info!("新建知识库配置成功: {}", req.file_name);
+    reload_after_change(&pool).await;
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "success": true,
+        "id": id,
+        "file_name": req.file_name,
+        "revision": revision,
+    })))
+}
+
+// 更新知识库配置内容（整体替换）
+#[put("/api/knowledge/configs/{file_name}")]
+pub async fn update_config(
+    pool: Option<web::Data<DbPool>>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: web::Json<KnowledgeConfigContent>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    let content = req.into_inner();
+    validate_file_name(&file_name)?;
+    validate_content(&content)?;
+
+    let pool = require_pool(pool)?;
+    let txn = pool.begin().await?;
+    KnowledgeConfigRepo::new(&txn)
+        .update(&file_name, content.into_new_config(file_name.clone()))
+        .await?;
+    let revision = record_revision(&txn, &file_name, "update", request_author(&http_req)).await?;
+    commit(txn).await?;
+    info!("更新知识库配置成功: {}", file_name);
+    reload_after_change(&pool).await;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true, "revision": revision })))
+}
+
+// 启用/停用知识库配置
+#[put("/api/knowledge/configs/{file_name}/active")]
+pub async fn update_config_active(
+    pool: Option<web::Data<DbPool>>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: web::Json<UpdateKnowledgeActiveRequest>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let txn = pool.begin().await?;
+    KnowledgeConfigRepo::new(&txn)
+        .update_active(&file_name, req.is_active)
+        .await?;
+    let revision = record_revision(&txn, &file_name, "active", request_author(&http_req)).await?;
+    commit(txn).await?;
+    info!("更新知识库配置状态成功: {} -> {}", file_name, req.is_active);
+    reload_after_change(&pool).await;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true, "revision": revision })))
+}
+
+// 删除知识库配置
+#[delete("/api/knowledge/configs/{file_name}")]
+pub async fn delete_config(
+    pool: Option<web::Data<DbPool>>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let txn = pool.begin().await?;
+
+    // 删除前保留最后一份快照，便于追溯
+    record_revision(&txn, &file_name, "delete", request_author(&http_req)).await?;
+    KnowledgeConfigRepo::new(&txn)
+        .delete_by_file_name(&file_name)
+        .await?;
+    commit(txn).await?;
+    info!("删除知识库配置成功: {}", file_name);
+    reload_after_change(&pool).await;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true })))
+}
+
+/// CSV 上传模式：预览只做校验，替换会覆盖已存储的数据
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadMode {
+    #[default]
+    Preview,
+    Replace,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadDataQuery {
+    #[serde(default)]
+    pub mode: UploadMode,
+    #[serde(default)]
+    pub preview_rows: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadDataResponse {
+    pub success: bool,
+    /// 数据是否已写入配置
+    pub stored: bool,
+    pub columns: Vec<String>,
+    pub preview: Vec<Vec<String>>,
+    pub total_rows: usize,
+}
+
+/// 读取 multipart 中名为 `file` 的字段内容
+async fn read_upload_file(mut payload: Multipart) -> Result<Vec<u8>, AppError> {
+    let mut file = None;
+    while let Some(field) = payload.next().await {
+        let mut field =
+            field.map_err(|e| AppError::validation(format!("读取上传内容失败: {e}")))?;
+        let is_file = field.name() == Some("file");
+
+        let mut buf = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk =
+                chunk.map_err(|e| AppError::validation(format!("读取上传内容失败: {e}")))?;
+            if buf.len() + chunk.len() > MAX_UPLOAD_BYTES {
+                return Err(AppError::validation(format!(
+                    "上传文件超过 {} MB 上限",
+                    MAX_UPLOAD_BYTES / 1024 / 1024
+                )));
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        if is_file {
+            file = Some(buf);
+        }
+    }
+    file.ok_or_else(|| AppError::validation("缺少上传文件字段 file"))
+}
+
+// 上传 CSV（支持 gzip）：按建表 SQL 校验列，预览或替换知识库数据
+#[post("/api/knowledge/configs/{file_name}/data")]
+pub async fn upload_data(
+    pool: Option<web::Data<DbPool>>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<UploadDataQuery>,
+    payload: Multipart,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let config = KnowledgeConfigRepo::new(pool.inner())
+        .find_by_file_name(&file_name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("知识库配置 {file_name}")))?;
+
+    let bytes = read_upload_file(payload).await?;
+    let expected = config
+        .create_sql
+        .as_deref()
+        .map(csv_import::table_columns)
+        .unwrap_or_default();
+    let table = web::block(move || {
+        let text = csv_import::decode_upload(&bytes)?;
+        csv_import::parse_csv(&text, &expected)
+    })
+    .await
+    .map_err(AppError::internal)??;
+
+    let stored = query.mode == UploadMode::Replace;
+    if stored {
+        let txn = pool.begin().await?;
+        KnowledgeConfigRepo::new(&txn)
+            .update_data_content(&file_name, table.to_csv()?)
+            .await?;
+        record_revision(&txn, &file_name, "upload", request_author(&http_req)).await?;
+        commit(txn).await?;
+        info!(
+            "替换知识库数据成功: {}，共 {} 行",
+            file_name,
+            table.rows.len()
+        );
+        reload_after_change(&pool).await;
+    }
+
+    let preview_rows = query.preview_rows.unwrap_or(DEFAULT_PREVIEW_ROWS);
+    Ok(HttpResponse::Ok().json(UploadDataResponse {
+        success: true,
+        stored,
+        total_rows: table.rows.len(),
+        preview: table.rows.into_iter().take(preview_rows).collect(),
+        columns: table.columns,
+    }))
+}
+
+/// 修订摘要，列表中不返回大字段内容
+#[derive(Debug, Serialize)]
+pub struct RevisionSummary {
+    pub revision: i32,
+    pub action: String,
+    pub author: Option<String>,
+    pub is_active: bool,
+    /// CSV 数据字节数
+    pub data_size: usize,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<KnowledgeConfigRevision> for RevisionSummary {
+    fn from(revision: KnowledgeConfigRevision) -> Self {
+        Self {
+            revision: revision.revision,
+            action: revision.action,
+            author: revision.author,
+            is_active: revision.is_active,
+            data_size: revision.data_content.as_deref().map_or(0, str::len),
+            created_at: revision.created_at,
         }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub changed: bool,
+    /// 统一格式 diff，未变化时为空
+    pub diff: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackRequest {
+    pub revision: i32,
+}
+
+async fn load_revision(
+    repo: &KnowledgeHistoryRepo<'_>,
+    file_name: &str,
+    revision: i32,
+) -> Result<KnowledgeConfigRevision, AppError> {
+    repo.find_revision(file_name, revision)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("知识库配置 {file_name} 的修订 {revision}")))
+}
+
+// 查询知识库配置的修订历史（按修订号倒序）
+#[get("/api/knowledge/configs/{file_name}/history")]
+pub async fn list_revisions(
+    pool: Option<web::Data<DbPool>>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let revisions = KnowledgeHistoryRepo::new(pool.inner())
+        .find_by_file_name(&file_name)
+        .await?;
+    if revisions.is_empty() {
+        return Err(AppError::not_found(format!(
+            "知识库配置 {file_name} 的修订历史"
+        )));
+    }
+
+    let revisions: Vec<RevisionSummary> = revisions.into_iter().map(Into::into).collect();
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+// 对比两个修订，逐字段返回统一格式 diff
+#[get("/api/knowledge/configs/{file_name}/history/diff")]
+pub async fn diff_revisions(
+    pool: Option<web::Data<DbPool>>,
+    path: web::Path<String>,
+    query: web::Query<RevisionDiffQuery>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let repo = KnowledgeHistoryRepo::new(pool.inner());
+    let from = load_revision(&repo, &file_name, query.from).await?;
+    let to = load_revision(&repo, &file_name, query.to).await?;
+
+    let from_label = format!("{file_name}@{}", from.revision);
+    let to_label = format!("{file_name}@{}", to.revision);
+    let diffs: Vec<FieldDiff> = [
+        ("config_content", &from.config_content, &to.config_content),
+        ("create_sql", &from.create_sql, &to.create_sql),
+        ("insert_sql", &from.insert_sql, &to.insert_sql),
+        ("data_content", &from.data_content, &to.data_content),
+    ]
+    .into_iter()
+    .map(|(field, old, new)| {
+        let diff = unified_diff(
+            old.as_deref().unwrap_or_default(),
+            new.as_deref().unwrap_or_default(),
+            &from_label,
+            &to_label,
+        );
+        FieldDiff {
+            field,
+            changed: old != new,
+            diff,
+        }
+    })
+    .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "from": from.revision,
+        "to": to.revision,
+        "is_active_changed": from.is_active != to.is_active,
+        "diffs": diffs,
+    })))
+}
+
+// 回滚到指定修订：恢复内容与启用状态，并生成一条新的修订
+#[post("/api/knowledge/configs/{file_name}/rollback")]
+pub async fn rollback_config(
+    pool: Option<web::Data<DbPool>>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: web::Json<RollbackRequest>,
+) -> Result<HttpResponse, AppError> {
+    let file_name = path.into_inner();
+    validate_file_name(&file_name)?;
+    let pool = require_pool(pool)?;
+    let target = load_revision(
+        &KnowledgeHistoryRepo::new(pool.inner()),
+        &file_name,
+        req.revision,
+    )
+    .await?;
+
+    let txn = pool.begin().await?;
+    let repo = KnowledgeConfigRepo::new(&txn);
+    let content = KnowledgeConfigContent {
+        config_content: target.config_content,
+        create_sql: target.create_sql,
+        insert_sql: target.insert_sql,
+        data_content: target.data_content,
     };
+    repo.update(&file_name, content.into_new_config(file_name.clone()))
+        .await?;
+    repo.update_active(&file_name, target.is_active).await?;
+    let revision = record_revision(&txn, &file_name, "rollback", request_author(&http_req)).await?;
+    commit(txn).await?;
+    info!("知识库配置 {} 已回滚到修订 {}", file_name, req.revision);
+    reload_after_change(&pool).await;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "rolled_back_to": req.revision,
+        "revision": revision,
+    })))
+}
+
+fn default_sample_rows() -> usize {
+    DEFAULT_SAMPLE_ROWS
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SchemaQuery {
+    /// 只返回指定的表，缺省返回全部
+    #[serde(default)]
+    pub table: Option<String>,
+    /// 每张表的采样行数，上限为 `MAX_SAMPLE_ROWS`
+    #[serde(default = "default_sample_rows")]
+    pub sample_rows: usize,
+}
+
+// 浏览已加载的知识库表：列、行数、索引、采样数据及引用该表的 OML 模型
+#[get("/api/knowledge/schema")]
+pub async fn knowledge_schema(query: web::Query<SchemaQuery>) -> Result<HttpResponse, AppError> {
+    let SchemaQuery { table, sample_rows } = query.into_inner();
+
+    let timeout = QueryLimits::from_setting().timeout;
+    let mut tables = sql_guard::run_with_timeout(timeout, move || match table {
+        Some(table) => {
+            knowledge::table_schema(&table, sample_rows.min(MAX_SAMPLE_ROWS)).map(|t| vec![t])
+        }
+        None => knowledge::knowdb_schema(sample_rows),
+    })
+    .await?;
+
+    let oml_repo = PathBuf::from(Setting::load().repo.oml_rule_repo);
+    let mut refs = web::block(move || knowledge::oml_table_refs(&oml_repo))
+        .await
+        .map_err(AppError::internal)?;
+    for table in &mut tables {
+        table.referenced_by = refs
+            .remove(&table.name.to_ascii_lowercase())
+            .unwrap_or_default();
+    }
 
-    Ok(response)
+    Ok(HttpResponse::Ok().json(tables))
 }
@@ -4,6 +4,7 @@ use actix_web::{HttpResponse, Responder, get};
 use serde::Serialize;
 
 pub mod debug;
+pub mod knowledge;
 
 #[derive(Serialize)]
 struct VersionInfo {
@@ -20,4 +21,7 @@ pub async fn get_version() -> impl Responder {
     })
 }
 
-pub use debug::{debug_parse, debug_transform, decode_base64, oml_format, wpl_format};
+pub use debug::{
+    debug_parse, debug_transform, decode_base64, oml_format, oml_format_check, wpl_format,
+    wpl_format_check,
+};
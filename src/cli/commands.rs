@@ -0,0 +1,522 @@
+// 子命令实现：输出统一为 JSON，退出码见 cli 模块常量
+
+use super::{EXIT_CHECK_FAILED, EXIT_ERROR, EXIT_OK, OutputArgs};
+use crate::db::DbPool;
+use crate::error::AppError;
+use crate::lsp;
+use crate::server::{Setting, cases};
+use crate::utils::diagnostic::{Diagnostic, OML_SYNTAX, SourceRange, WPL_SYNTAX};
+use crate::utils::format_options::FormatCheck;
+use crate::utils::oml::strip_comments;
+use crate::utils::{EventIdMode, ParseOptions, warp_check_batch, warp_check_outcome};
+use crate::{FormatOptions, OmlFormatter, WplFormatter};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wp_data_utils::cache::FieldQueryCache;
+use wp_lang::WplCode;
+use wp_model_core::model::DataRecord;
+use wp_oml::{core::DataTransformer, parser::oml_parse};
+
+/// 规则文件类型，按扩展名识别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    Wpl,
+    Oml,
+}
+
+impl RuleKind {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wpl") => Some(RuleKind::Wpl),
+            Some("oml") => Some(RuleKind::Oml),
+            _ => None,
+        }
+    }
+}
+
+impl OutputArgs {
+    fn parse_options(&self) -> ParseOptions {
+        let event_id = match (self.no_event_id, self.event_id_base) {
+            (true, _) => EventIdMode::Omit,
+            (false, Some(base)) => EventIdMode::Fixed(base),
+            (false, None) => EventIdMode::Auto,
+        };
+        ParseOptions {
+            event_id,
+            keep_ignore: self.keep_ignore,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TransformItem {
+    index: usize,
+    line: usize,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<DataRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TransformReport {
+    total: usize,
+    success: usize,
+    failed: usize,
+    items: Vec<TransformItem>,
+}
+
+#[derive(Serialize)]
+struct FmtItem {
+    file: String,
+    kind: RuleKind,
+    /// 文件是否已是格式化后的内容
+    formatted: bool,
+    /// `check` 模式下未格式化文件的统一 diff
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+    /// 无法格式化的原因，如规则语法错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// 错误在规则文件中的位置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<SourceRange>,
+}
+
+#[derive(Serialize)]
+struct CheckItem {
+    file: String,
+    kind: RuleKind,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// 错误在规则文件中的位置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<SourceRange>,
+}
+
+#[derive(Serialize)]
+struct CheckReport {
+    total: usize,
+    failed: usize,
+    items: Vec<CheckItem>,
+}
+
+/// 使用 WPL 规则解析样本文件
+pub fn run_parse(wpl: &Path, input: &Path, options: &OutputArgs) -> i32 {
+    let (wpl_code, data) = match (read_file(wpl), read_file(input)) {
+        (Ok(wpl_code), Ok(data)) => (wpl_code, data),
+        (Err(e), _) | (_, Err(e)) => return fail(e),
+    };
+    let parse_options = options.parse_options();
+
+    if options.single {
+        return match warp_check_outcome(&wpl_code, &data, &parse_options) {
+            Ok(outcome) => {
+                print_json(&outcome.record);
+                EXIT_OK
+            }
+            Err(e) => {
+                print_json(&serde_json::json!({ "success": false, "error": e.to_string() }));
+                EXIT_CHECK_FAILED
+            }
+        };
+    }
+
+    match warp_check_batch(
+        &wpl_code,
+        &data,
+        options.delimiter.as_deref(),
+        &parse_options,
+    ) {
+        Ok(result) => {
+            print_json(&result);
+            if result.failed > 0 {
+                EXIT_CHECK_FAILED
+            } else {
+                EXIT_OK
+            }
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// 先经 WPL 解析，再执行 OML 转换
+pub fn run_transform(oml: &Path, wpl: &Path, input: &Path, options: &OutputArgs) -> i32 {
+    let (oml_code, wpl_code, data) = match (read_file(oml), read_file(wpl), read_file(input)) {
+        (Ok(oml_code), Ok(wpl_code), Ok(data)) => (oml_code, wpl_code, data),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return fail(e),
+    };
+    let parse_options = options.parse_options();
+
+    // OML 只解析一次，所有事件复用同一模型与查询缓存
+    let filter_oml = strip_comments(&oml_code);
+    let model = match oml_parse(&mut filter_oml.as_str(), "") {
+        Ok(model) => model,
+        Err(e) => return fail(AppError::oml_syntax(&oml_code, e)),
+    };
+    let mut cache = FieldQueryCache::with_capacity(10);
+
+    // 统一为 (序号, 行号, 解析结果)，单条与批量模式共用后续转换逻辑
+    let parsed: Vec<(usize, usize, Result<DataRecord, String>)> = if options.single {
+        let record = warp_check_outcome(&wpl_code, &data, &parse_options)
+            .map(|outcome| outcome.record)
+            .map_err(|e| e.to_string());
+        vec![(1, 1, record)]
+    } else {
+        match warp_check_batch(
+            &wpl_code,
+            &data,
+            options.delimiter.as_deref(),
+            &parse_options,
+        ) {
+            Ok(result) => result
+                .items
+                .into_iter()
+                .map(|item| {
+                    let record = item.record.ok_or_else(|| item.error.unwrap_or_default());
+                    (item.index, item.line, record)
+                })
+                .collect(),
+            Err(e) => return fail(e),
+        }
+    };
+
+    let items: Vec<TransformItem> = parsed
+        .into_iter()
+        .map(|(index, line, record)| {
+            match record.map(|record| model.transform_ref(&record, &mut cache)) {
+                Ok(record) => TransformItem {
+                    index,
+                    line,
+                    success: true,
+                    fields: Some(record),
+                    error: None,
+                },
+                Err(e) => TransformItem {
+                    index,
+                    line,
+                    success: false,
+                    fields: None,
+                    error: Some(e),
+                },
+            }
+        })
+        .collect();
+
+    let success = items.iter().filter(|item| item.success).count();
+    let report = TransformReport {
+        total: items.len(),
+        success,
+        failed: items.len() - success,
+        items,
+    };
+    print_json(&report);
+    if report.failed > 0 {
+        EXIT_CHECK_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+/// 格式化规则文件；`check` 模式只检查不写回，并给出未格式化文件的 diff。
+///
+/// 存在语法错误等无法格式化的文件，或 `check` 模式下存在未格式化文件时返回检查失败。
+/// 格式化风格读取配置中 WPL/OML 规则仓库的 `.wpfmt.toml`。
+pub fn run_fmt(paths: &[PathBuf], check: bool) -> i32 {
+    let files = match collect_rule_files(paths) {
+        Ok(files) => files,
+        Err(e) => return fail(e),
+    };
+
+    let setting = Setting::load();
+    let load_options = |repo: &str| FormatOptions::load(Path::new(repo));
+    let (wpl_options, oml_options) = match (
+        load_options(&setting.repo.wpl_rule_repo),
+        load_options(&setting.repo.oml_rule_repo),
+    ) {
+        (Ok(wpl), Ok(oml)) => (wpl, oml),
+        (Err(e), _) | (_, Err(e)) => return fail(e),
+    };
+    let wpl_formatter = WplFormatter::with_options(wpl_options);
+    let oml_formatter = OmlFormatter::with_options(oml_options);
+    let mut items = Vec::with_capacity(files.len());
+    for (path, kind) in files {
+        let content = match read_file(&path) {
+            Ok(content) => content,
+            Err(e) => return fail(e),
+        };
+        let file = path.display().to_string();
+        let formatted = match kind {
+            RuleKind::Wpl => wpl_formatter.format(&content),
+            RuleKind::Oml => oml_formatter.format(&content),
+        };
+        let formatted = match formatted {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                let range = match &e {
+                    AppError::Diagnostics { diagnostics, .. } => {
+                        diagnostics.first().and_then(|d| d.range)
+                    }
+                    _ => None,
+                };
+                items.push(FmtItem {
+                    file,
+                    kind,
+                    formatted: false,
+                    diff: None,
+                    error: Some(e.to_string()),
+                    range,
+                });
+                continue;
+            }
+        };
+
+        let result = FormatCheck::new(&content, &formatted, &file);
+        if !result.formatted
+            && !check
+            && let Err(e) = fs::write(&path, &formatted)
+        {
+            return fail(format!("{}: {e}", path.display()));
+        }
+        items.push(FmtItem {
+            file,
+            kind,
+            formatted: result.formatted,
+            diff: (check && !result.formatted).then_some(result.diff),
+            error: None,
+            range: None,
+        });
+    }
+
+    print_json(&items);
+    let failed = items
+        .iter()
+        .any(|item| item.error.is_some() || (check && !item.formatted));
+    if failed { EXIT_CHECK_FAILED } else { EXIT_OK }
+}
+
+/// 校验规则仓库中所有 WPL/OML 文件能否被引擎解析
+pub fn run_check(wpl_repo: Option<PathBuf>, oml_repo: Option<PathBuf>) -> i32 {
+    let setting = Setting::load();
+    let wpl_repo = wpl_repo.unwrap_or_else(|| PathBuf::from(&setting.repo.wpl_rule_repo));
+    let oml_repo = oml_repo.unwrap_or_else(|| PathBuf::from(&setting.repo.oml_rule_repo));
+
+    let files = match collect_rule_files(&[wpl_repo, oml_repo]) {
+        Ok(files) => files,
+        Err(e) => return fail(e),
+    };
+
+    let items: Vec<CheckItem> = files
+        .into_iter()
+        .map(|(path, kind)| {
+            let (error, range) = match read_file(&path) {
+                Ok(content) => match check_rule(&path, kind, &content) {
+                    Ok(()) => (None, None),
+                    Err(diagnostic) => (Some(diagnostic.message), diagnostic.range),
+                },
+                Err(e) => (Some(e), None),
+            };
+            CheckItem {
+                file: path.display().to_string(),
+                kind,
+                ok: error.is_none(),
+                error,
+                range,
+            }
+        })
+        .collect();
+
+    let report = CheckReport {
+        total: items.len(),
+        failed: items.iter().filter(|item| !item.ok).count(),
+        items,
+    };
+    print_json(&report);
+    if report.failed > 0 {
+        EXIT_CHECK_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+/// 运行规则仓库中的回归用例
+pub fn run_test(wpl_repo: Option<PathBuf>, oml_repo: Option<PathBuf>) -> i32 {
+    let setting = Setting::load();
+    let wpl_repo = wpl_repo.unwrap_or_else(|| PathBuf::from(&setting.repo.wpl_rule_repo));
+    let oml_repo = oml_repo.unwrap_or_else(|| PathBuf::from(&setting.repo.oml_rule_repo));
+
+    let repo_display = wpl_repo.display().to_string();
+    match cases::run_cases(wpl_repo, oml_repo) {
+        // 没有任何用例时视为失败，避免 CI 在仓库配置错误时误报通过
+        Ok(report) if report.total == 0 => fail(format!(
+            "{repo_display} 下未找到任何回归用例（{}）",
+            cases::CASE_FILE_NAME
+        )),
+        Ok(report) => {
+            print_json(&report);
+            if report.failed > 0 {
+                EXIT_CHECK_FAILED
+            } else {
+                EXIT_OK
+            }
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// 执行或查看数据库迁移
+pub async fn run_migrate(status: bool) -> i32 {
+    let Some(conf) = Setting::load().database else {
+        return fail("未配置 [database]，无法执行迁移");
+    };
+    let pool = match DbPool::new(&conf.url, conf.max_connections, conf.min_connections).await {
+        Ok(pool) => pool,
+        Err(e) => return fail(e),
+    };
+
+    let result = if status {
+        pool.migration_status()
+            .await
+            .map(|status| serde_json::json!(status))
+    } else {
+        pool.migrate()
+            .await
+            .map(|applied| serde_json::json!({ "success": true, "applied": applied }))
+    };
+    match result {
+        Ok(output) => {
+            print_json(&output);
+            EXIT_OK
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// 运行 stdio 语言服务器；stdout 为协议通道，错误只输出到 stderr
+pub async fn run_lsp() -> i32 {
+    let result = match tokio::task::spawn_blocking(lsp::run).await {
+        Ok(result) => result,
+        Err(e) => Err(e.into()),
+    };
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("语言服务器异常退出: {e}");
+            EXIT_ERROR
+        }
+    }
+}
+
+/// 校验单个规则文件能否被引擎解析
+fn check_rule(path: &Path, kind: RuleKind, content: &str) -> Result<(), Diagnostic> {
+    match kind {
+        RuleKind::Wpl => {
+            let code = WplCode::build(path.to_path_buf(), content)
+                .map_err(|e| Diagnostic::syntax(WPL_SYNTAX, content, e))?;
+            code.parse_pkg()
+                .map_err(|e| Diagnostic::syntax(WPL_SYNTAX, content, e))?;
+        }
+        RuleKind::Oml => {
+            let filter_oml = strip_comments(content);
+            oml_parse(&mut filter_oml.as_str(), "")
+                .map_err(|e| Diagnostic::syntax(OML_SYNTAX, content, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// 递归收集 `.wpl` / `.oml` 文件，结果按路径排序
+fn collect_rule_files(paths: &[PathBuf]) -> Result<Vec<(PathBuf, RuleKind)>, String> {
+    fn walk(path: &Path, files: &mut Vec<(PathBuf, RuleKind)>) -> Result<(), String> {
+        if path.is_file() {
+            if let Some(kind) = RuleKind::from_path(path) {
+                files.push((path.to_path_buf(), kind));
+            }
+            return Ok(());
+        }
+        let entries = path
+            .read_dir()
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        for entry in entries.flatten() {
+            walk(&entry.path(), files)?;
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.exists() {
+            return Err(format!("路径不存在: {}", path.display()));
+        }
+        walk(path, &mut files)?;
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// 以 JSON 格式输出结果到标准输出
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("序列化输出失败: {e}"),
+    }
+}
+
+/// 以 JSON 格式输出错误并返回错误退出码
+fn fail(e: impl std::fmt::Display) -> i32 {
+    print_json(&serde_json::json!({
+        "success": false,
+        "error": e.to_string(),
+    }));
+    EXIT_ERROR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn collect_rule_files_filters_by_extension_and_sorts() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("pkg");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("b.wpl"), "").unwrap();
+        fs::write(dir.path().join("a.oml"), "").unwrap();
+        fs::write(dir.path().join("cases.toml"), "").unwrap();
+
+        let files = collect_rule_files(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                (dir.path().join("a.oml"), RuleKind::Oml),
+                (nested.join("b.wpl"), RuleKind::Wpl),
+            ]
+        );
+        assert!(collect_rule_files(&[dir.path().join("missing")]).is_err());
+    }
+
+    #[test]
+    fn check_rule_reports_invalid_wpl() {
+        let path = Path::new("demo.wpl");
+        assert!(
+            check_rule(
+                path,
+                RuleKind::Wpl,
+                "package demo { rule r { (digit:id) } }"
+            )
+            .is_ok()
+        );
+        assert!(check_rule(path, RuleKind::Wpl, "package demo { rule r { (digit:id }").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+// 命令行模块：无需启动 Web 服务即可执行的子命令
+
+mod commands;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use std::path::PathBuf;
+
+/// 执行成功
+pub const EXIT_OK: i32 = 0;
+/// 检查未通过（用例失败等）
+pub const EXIT_CHECK_FAILED: i32 = 1;
+/// 执行出错（文件读取失败、参数错误等）
+pub const EXIT_ERROR: i32 = 2;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 启动 Web 服务（默认）
+    Serve,
+    /// 使用 WPL 规则解析样本文件，输出解析结果
+    Parse {
+        /// WPL 规则文件
+        #[arg(long)]
+        wpl: PathBuf,
+        /// 样本日志文件
+        #[arg(long)]
+        input: PathBuf,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// 解析样本后执行 OML 转换，输出转换结果
+    Transform {
+        /// OML 模型文件
+        #[arg(long)]
+        oml: PathBuf,
+        /// WPL 规则文件
+        #[arg(long)]
+        wpl: PathBuf,
+        /// 样本日志文件
+        #[arg(long)]
+        input: PathBuf,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// 格式化 WPL/OML 文件（目录会递归处理）
+    Fmt {
+        /// 只检查是否已格式化并输出 diff，不写回文件
+        #[arg(long)]
+        check: bool,
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// 校验规则仓库中的所有 WPL/OML 文件
+    Check {
+        /// WPL 规则仓库目录，缺省读取配置文件
+        #[arg(long)]
+        wpl_repo: Option<PathBuf>,
+        /// OML 规则仓库目录，缺省读取配置文件
+        #[arg(long)]
+        oml_repo: Option<PathBuf>,
+    },
+    /// 执行知识库数据库迁移（需配置 [database]）
+    Migrate {
+        /// 只输出已执行/待执行的迁移，不实际执行
+        #[arg(long)]
+        status: bool,
+    },
+    /// 以 stdio 方式启动 WPL/OML 语言服务器（LSP）
+    Lsp,
+    /// 运行规则仓库中 cases.toml 定义的回归用例；仓库路径无效或没有任何用例时以非零状态退出
+    Test {
+        /// WPL 规则仓库目录，缺省读取配置文件
+        #[arg(long)]
+        wpl_repo: Option<PathBuf>,
+        /// OML 规则仓库目录，缺省读取配置文件
+        #[arg(long)]
+        oml_repo: Option<PathBuf>,
+    },
+}
+
+/// parse/transform 共用的解析参数
+#[derive(ClapArgs, Debug)]
+pub struct OutputArgs {
+    /// 整个输入作为一条事件解析（默认按行切分）
+    #[arg(long, conflicts_with = "delimiter")]
+    pub single: bool,
+    /// 多条事件的分隔符，缺省按行切分
+    #[arg(long)]
+    pub delimiter: Option<String>,
+    /// 使用固定的事件 ID 起始值，保证多次运行输出一致
+    #[arg(long, conflicts_with = "no_event_id")]
+    pub event_id_base: Option<i64>,
+    /// 不输出 wp_event_id 字段
+    #[arg(long)]
+    pub no_event_id: bool,
+    /// 保留被忽略的字段
+    #[arg(long)]
+    pub keep_ignore: bool,
+}
+
+/// 执行非 serve 子命令，返回进程退出码
+pub async fn run(command: Command) -> i32 {
+    match command {
+        Command::Serve => EXIT_OK,
+        Command::Parse { wpl, input, output } => commands::run_parse(&wpl, &input, &output),
+        Command::Transform {
+            oml,
+            wpl,
+            input,
+            output,
+        } => commands::run_transform(&oml, &wpl, &input, &output),
+        Command::Fmt { paths, check } => commands::run_fmt(&paths, check),
+        Command::Check { wpl_repo, oml_repo } => commands::run_check(wpl_repo, oml_repo),
+        Command::Test { wpl_repo, oml_repo } => commands::run_test(wpl_repo, oml_repo),
+        Command::Migrate { status } => commands::run_migrate(status).await,
+        Command::Lsp => commands::run_lsp().await,
+    }
+}
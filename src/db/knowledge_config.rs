@@ -2,7 +2,9 @@
 
 use crate::error::{DbError, DbResult};
 use chrono::Utc;
-use sea_orm::{DatabaseConnection, QueryOrder, Set, entity::prelude::*};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, QueryOrder, QuerySelect, Set, entity::prelude::*,
+};
 use serde::{Deserialize, Serialize};
 use wp_editor_migrations::entity::knowledge_config::{ActiveModel, Column, Entity, Model};
 
@@ -17,12 +19,13 @@ pub struct NewKnowledgeConfig {
     pub data_content: Option<String>,
 }
 
-pub struct KnowledgeConfigRepo<'a> {
-    db: &'a DatabaseConnection,
+/// 可基于连接池或事务（`DatabaseTransaction`）构建
+pub struct KnowledgeConfigRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
 }
 
-impl<'a> KnowledgeConfigRepo<'a> {
-    pub fn new(db: &'a DatabaseConnection) -> Self {
+impl<'a, C: ConnectionTrait> KnowledgeConfigRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
         Self { db }
     }
 
@@ -42,6 +45,18 @@ impl<'a> KnowledgeConfigRepo<'a> {
         Ok(config)
     }
 
+    /// 在事务中锁定配置行（`SELECT ... FOR UPDATE`），串行化同一配置的并发变更。
+    ///
+    /// SQLite 不支持行锁，由其库级写锁保证串行。
+    pub async fn lock(&self, file_name: &str) -> DbResult<Option<KnowledgeConfig>> {
+        let config = Entity::find()
+            .filter(Column::FileName.eq(file_name))
+            .lock_exclusive()
+            .one(self.db)
+            .await?;
+        Ok(config)
+    }
+
     pub async fn create(&self, config: NewKnowledgeConfig) -> DbResult<i32> {
         let now = Utc::now();
         let active_model = ActiveModel {
@@ -90,6 +105,20 @@ impl<'a> KnowledgeConfigRepo<'a> {
         Ok(())
     }
 
+    pub async fn update_data_content(&self, file_name: &str, data_content: String) -> DbResult<()> {
+        let model = Entity::find()
+            .filter(Column::FileName.eq(file_name))
+            .one(self.db)
+            .await?
+            .ok_or(DbError::not_found("知识库配置"))?;
+
+        let mut active_model: ActiveModel = model.into();
+        active_model.data_content = Set(Some(data_content));
+        active_model.updated_at = Set(Utc::now());
+        active_model.update(self.db).await?;
+        Ok(())
+    }
+
     pub async fn get_status_list(&self) -> DbResult<Vec<(String, bool)>> {
         // 只返回处于激活状态的配置，保持与 wpl/oml 一致的行为
         let configs = Entity::find()
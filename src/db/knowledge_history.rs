@@ -0,0 +1,86 @@
+// 知识库配置修订历史仓储
+
+use crate::db::knowledge_config::KnowledgeConfig;
+use crate::error::DbResult;
+use chrono::Utc;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, QueryOrder, QuerySelect, Set, entity::prelude::*,
+};
+use wp_editor_migrations::entity::knowledge_config_history::{ActiveModel, Column, Entity, Model};
+
+pub type KnowledgeConfigRevision = Model;
+
+/// 可基于连接池或事务（`DatabaseTransaction`）构建
+pub struct KnowledgeHistoryRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> KnowledgeHistoryRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// 以配置当前内容生成一条新修订，返回修订号。
+    ///
+    /// 修订号取当前最大值加一，需与配置变更在同一事务中调用，并先通过
+    /// [`KnowledgeConfigRepo::lock`](crate::db::KnowledgeConfigRepo::lock) 锁定配置行，
+    /// 避免并发变更分配到相同的修订号。
+    pub async fn record(
+        &self,
+        config: &KnowledgeConfig,
+        action: &str,
+        author: Option<String>,
+    ) -> DbResult<i32> {
+        let latest: Option<i32> = Entity::find()
+            .select_only()
+            .column_as(Column::Revision.max(), "revision")
+            .filter(Column::FileName.eq(&config.file_name))
+            .into_tuple()
+            .one(self.db)
+            .await?
+            .flatten();
+        let revision = latest.unwrap_or(0) + 1;
+
+        let active_model = ActiveModel {
+            file_name: Set(config.file_name.clone()),
+            revision: Set(revision),
+            action: Set(action.to_string()),
+            author: Set(author),
+            config_content: Set(config.config_content.clone()),
+            create_sql: Set(config.create_sql.clone()),
+            insert_sql: Set(config.insert_sql.clone()),
+            data_content: Set(config.data_content.clone()),
+            is_active: Set(config.is_active),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        };
+        Entity::insert(active_model).exec(self.db).await?;
+        Ok(revision)
+    }
+
+    /// 按修订号倒序列出配置的全部修订
+    pub async fn find_by_file_name(
+        &self,
+        file_name: &str,
+    ) -> DbResult<Vec<KnowledgeConfigRevision>> {
+        let revisions = Entity::find()
+            .filter(Column::FileName.eq(file_name))
+            .order_by_desc(Column::Revision)
+            .all(self.db)
+            .await?;
+        Ok(revisions)
+    }
+
+    pub async fn find_revision(
+        &self,
+        file_name: &str,
+        revision: i32,
+    ) -> DbResult<Option<KnowledgeConfigRevision>> {
+        let revision = Entity::find()
+            .filter(Column::FileName.eq(file_name))
+            .filter(Column::Revision.eq(revision))
+            .one(self.db)
+            .await?;
+        Ok(revision)
+    }
+}
@@ -1,5 +1,9 @@
 // 数据库模块
 
+pub mod knowledge_config;
+pub mod knowledge_history;
 pub mod pool;
 
+pub use knowledge_config::{KnowledgeConfig, KnowledgeConfigRepo, NewKnowledgeConfig};
+pub use knowledge_history::{KnowledgeConfigRevision, KnowledgeHistoryRepo};
 pub use pool::DbPool;
@@ -1,8 +1,20 @@
 // 数据库连接池抽象
 
-use crate::error::DbResult;
-use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use crate::error::{DbError, DbResult};
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DatabaseTransaction, TransactionTrait,
+};
+use serde::Serialize;
+use std::path::Path;
 use std::time::Duration;
+use wp_editor_migrations::{Migrator, MigratorTrait};
+
+/// 迁移状态：已执行与待执行的迁移名称
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub applied: Vec<String>,
+    pub pending: Vec<String>,
+}
 
 #[derive(Clone)]
 pub struct DbPool {
@@ -13,10 +25,12 @@ impl DbPool {
     pub async fn new(
         database_url: &str,
         max_connections: u32,
-        _min_connections: u32,
+        min_connections: u32,
     ) -> DbResult<Self> {
-        let mut opt = ConnectOptions::new(database_url.to_string());
+        let database_url = prepare_sqlite_url(database_url)?;
+        let mut opt = ConnectOptions::new(database_url);
         opt.max_connections(max_connections)
+            .min_connections(min_connections)
             .connect_timeout(Duration::from_secs(30))
             .idle_timeout(Duration::from_secs(600))
             .sqlx_logging(false);
@@ -29,8 +43,70 @@ impl DbPool {
         &self.conn
     }
 
+    /// 开启事务，未提交即丢弃时自动回滚
+    pub async fn begin(&self) -> DbResult<DatabaseTransaction> {
+        Ok(self.conn.begin().await?)
+    }
+
     pub async fn test_connection(&self) -> DbResult<()> {
         self.conn.ping().await?;
         Ok(())
     }
+
+    /// 执行全部待执行的迁移，返回本次执行的迁移名称
+    pub async fn migrate(&self) -> DbResult<Vec<String>> {
+        let pending = self.migration_status().await?.pending;
+        Migrator::up(&self.conn, None).await?;
+        Ok(pending)
+    }
+
+    pub async fn migration_status(&self) -> DbResult<MigrationStatus> {
+        let applied = Migrator::get_applied_migrations(&self.conn)
+            .await?
+            .iter()
+            .map(|m| m.name().to_string())
+            .collect();
+        let pending = Migrator::get_pending_migrations(&self.conn)
+            .await?
+            .iter()
+            .map(|m| m.name().to_string())
+            .collect();
+        Ok(MigrationStatus { applied, pending })
+    }
+}
+
+/// SQLite 连接串预处理：确保数据库文件所在目录存在，并在未指定 `mode` 时自动创建文件。
+///
+/// 支持 `sqlite://path/to/file.db` 与 `sqlite:path` 两种写法；非 SQLite 或内存库原样返回。
+fn prepare_sqlite_url(database_url: &str) -> DbResult<String> {
+    let Some(rest) = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))
+    else {
+        return Ok(database_url.to_string());
+    };
+
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if path.is_empty() || path == ":memory:" || query.contains("mode=memory") {
+        return Ok(database_url.to_string());
+    }
+
+    if let Some(parent) = Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            DbError::Db(sea_orm::DbErr::Custom(format!(
+                "创建 SQLite 数据目录 {} 失败: {e}",
+                parent.display()
+            )))
+        })?;
+    }
+
+    if query.split('&').any(|param| param.starts_with("mode=")) {
+        Ok(database_url.to_string())
+    } else if query.is_empty() {
+        Ok(format!("{database_url}?mode=rwc"))
+    } else {
+        Ok(format!("{database_url}&mode=rwc"))
+    }
 }
@@ -1,4 +1,6 @@
+use crate::utils::diagnostic::{Diagnostic, OML_SYNTAX, WPL_DATA, WPL_SYNTAX};
 use actix_web::{HttpResponse, ResponseError};
+use orion_error::UvsReason;
 use serde::Serialize;
 use std::fmt::Display;
 use wp_error::OMLCodeError;
@@ -47,6 +49,14 @@ pub enum AppError {
     #[error("WPL 解析失败: {0}")]
     WplParse(String),
 
+    /// 带结构化诊断的规则/数据错误，`code` 沿用对应的原有错误码
+    #[error("{message}")]
+    Diagnostics {
+        code: &'static str,
+        message: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+
     #[error("Wpl 解析失败: {0}")]
     WplParseOrion(#[from] orion_error::StructError<WparseReason>),
 
@@ -70,6 +80,19 @@ pub enum AppError {
 
     #[error("Base64 解码失败: {0}")]
     InvalidBase64(String),
+
+    // 知识库相关错误
+    #[error("知识库查询失败: {0}")]
+    KnowledgeQuery(String),
+
+    #[error("未配置知识库数据库，请在 config.toml 中添加 [database] 配置")]
+    DatabaseUnavailable,
+
+    #[error("不允许执行的 SQL: {0}")]
+    ForbiddenSql(String),
+
+    #[error("知识库查询超时（{0} ms）")]
+    QueryTimeout(u64),
 }
 
 impl AppError {
@@ -81,6 +104,14 @@ impl AppError {
         AppError::Internal(e.to_string())
     }
 
+    pub fn knowledge_query<E: Display>(e: E) -> Self {
+        AppError::KnowledgeQuery(e.to_string())
+    }
+
+    pub fn forbidden_sql(msg: impl Into<String>) -> Self {
+        AppError::ForbiddenSql(msg.into())
+    }
+
     pub fn git<E: Display>(e: E) -> Self {
         AppError::Git(e.to_string())
     }
@@ -99,7 +130,35 @@ impl AppError {
     ) -> Self {
         let hint_str = hint.into();
         let err_msg = format!("{}\n解析深度: {depth}\n{hint_str}", error);
-        AppError::WplParse(err_msg)
+        let mut diagnostic = Diagnostic::error(WPL_DATA, error.to_string());
+        if let WparseReason::Uvs(UvsReason::DataError(_, Some(pos))) = error.reason() {
+            diagnostic = diagnostic.with_log_offset(*pos);
+        }
+        AppError::Diagnostics {
+            code: "WPL_PARSE_ERROR",
+            message: AppError::WplParse(err_msg).to_string(),
+            diagnostics: vec![diagnostic],
+        }
+    }
+
+    /// WPL 规则语法错误，附带源码位置
+    pub fn wpl_syntax(source: &str, error: impl Display) -> Self {
+        let diagnostic = Diagnostic::syntax(WPL_SYNTAX, source, &error);
+        AppError::Diagnostics {
+            code: "WPL_PARSE_ERROR",
+            message: AppError::WplParse(error.to_string()).to_string(),
+            diagnostics: vec![diagnostic],
+        }
+    }
+
+    /// OML 模型语法错误，附带源码位置
+    pub fn oml_syntax(source: &str, error: OMLCodeError) -> Self {
+        let diagnostic = Diagnostic::syntax(OML_SYNTAX, source, &error);
+        AppError::Diagnostics {
+            code: "OML_PARSE_ORION_ERROR",
+            message: AppError::OmlParseOrion(error).to_string(),
+            diagnostics: vec![diagnostic],
+        }
     }
 
     pub fn wpl_parse_msg(msg: impl Into<String>) -> Self {
@@ -155,8 +214,13 @@ impl AppError {
             AppError::PortUnreachable { .. } => "PORT_UNREACHABLE",
             AppError::InvalidGitToken { .. } => "INVALID_GIT_TOKEN",
             AppError::InvalidBase64(_) => "INVALID_BASE64",
+            AppError::Diagnostics { code, .. } => *code,
             AppError::WplParseOrion(_) => "WPL_PARSE_ORION_ERROR",
             AppError::OmlParseOrion(_) => "OML_PARSE_ORION_ERROR",
+            AppError::KnowledgeQuery(_) => "KNOWLEDGE_QUERY_ERROR",
+            AppError::DatabaseUnavailable => "DATABASE_UNAVAILABLE",
+            AppError::ForbiddenSql(_) => "FORBIDDEN_SQL",
+            AppError::QueryTimeout(_) => "QUERY_TIMEOUT",
         }
     }
 }
@@ -170,15 +234,22 @@ impl ResponseError for AppError {
             AppError::InvalidConnection { .. }
             | AppError::Validation(_)
             | AppError::WplParse(_)
+            | AppError::Diagnostics { .. }
             | AppError::OmlTransform(_)
             | AppError::NoParseResult
             | AppError::PortUnreachable { .. }
             | AppError::OmlParseOrion(_)
             | AppError::WplParseOrion(_)
-            | AppError::InvalidGitToken { .. } => StatusCode::BAD_REQUEST,
+            | AppError::InvalidGitToken { .. }
+            | AppError::KnowledgeQuery(_) => StatusCode::BAD_REQUEST,
 
             // 403 Forbidden - 权限/关联错误
-            AppError::ConnectionMismatch { .. } => StatusCode::FORBIDDEN,
+            AppError::ConnectionMismatch { .. } | AppError::ForbiddenSql(_) => {
+                StatusCode::FORBIDDEN
+            }
+
+            // 408 Request Timeout - 查询超过配置的执行时间
+            AppError::QueryTimeout(_) => StatusCode::REQUEST_TIMEOUT,
 
             // 404 Not Found - 资源不存在
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
@@ -188,6 +259,9 @@ impl ResponseError for AppError {
 
             // 500 Bad Request - Base64 解码错误
             AppError::InvalidBase64(_) => StatusCode::INTERNAL_SERVER_ERROR,
+
+            // 503 Service Unavailable - 依赖的数据库未配置或不可用
+            AppError::DatabaseUnavailable => StatusCode::SERVICE_UNAVAILABLE,
         };
 
         let details = match self {
@@ -207,6 +281,12 @@ impl ResponseError for AppError {
                 Some(serde_json::json!({ "addr": addr, "reason": reason }))
             }
             AppError::InvalidGitToken { reason } => Some(serde_json::json!({ "reason": reason })),
+            AppError::Diagnostics { diagnostics, .. } => {
+                Some(serde_json::json!({ "diagnostics": diagnostics }))
+            }
+            AppError::QueryTimeout(timeout_ms) => {
+                Some(serde_json::json!({ "timeout_ms": timeout_ms }))
+            }
             _ => None,
         };
 
@@ -4,8 +4,10 @@
 extern crate tracing;
 
 pub mod api;
+pub mod cli;
 pub mod db;
 pub mod error;
+pub mod lsp;
 pub mod server;
 pub mod utils;
 
@@ -13,5 +15,7 @@ pub mod utils;
 pub use db::DbPool;
 pub use server::{Setting, WebConf};
 pub use utils::{
-    OmlFormatter, ParsedField, WplFormatter, convert_record, record_to_fields, warp_check_record,
+    FieldSpan, FormatOptions, OmlFormatter, ParsedField, WplFormatter, convert_record,
+    record_spans, record_to_fields, warp_check_batch, warp_check_outcome, warp_check_record,
+    warp_rule_matrix, warp_trace,
 };
@@ -0,0 +1,175 @@
+// 语言服务的纯分析逻辑：语言识别、位置换算、诊断与格式化，不涉及协议收发
+
+use crate::utils::diagnostic::{Diagnostic, OML_SYNTAX, Severity, WPL_SYNTAX, offset_of};
+use crate::utils::oml::strip_comments;
+use crate::{OmlFormatter, WplFormatter};
+use lsp_types::{DiagnosticSeverity, NumberOrString, Position, Range, TextEdit, Uri};
+use std::path::PathBuf;
+use wp_lang::WplCode;
+use wp_oml::parser::oml_parse;
+
+/// 诊断来源标识，显示在编辑器的问题面板中
+pub const DIAGNOSTIC_SOURCE: &str = "wp-editor";
+
+/// 语言服务支持的规则语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Wpl,
+    Oml,
+}
+
+impl Lang {
+    /// 按 `textDocument/didOpen` 中的 languageId 识别
+    pub fn from_language_id(id: &str) -> Option<Self> {
+        match id.to_ascii_lowercase().as_str() {
+            "wpl" => Some(Lang::Wpl),
+            "oml" => Some(Lang::Oml),
+            _ => None,
+        }
+    }
+
+    /// 按文档路径的扩展名识别
+    pub fn from_uri(uri: &Uri) -> Option<Self> {
+        let path = uri.path().as_str();
+        let (_, ext) = path.rsplit_once('.')?;
+        Self::from_language_id(ext)
+    }
+}
+
+/// 字节偏移与 LSP 位置互转；LSP 的列按 UTF-16 码元计数
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    /// 字节偏移所在的行号（从 0 开始）
+    pub fn line(&self, offset: usize) -> u32 {
+        let offset = offset.min(self.text.len());
+        (self.line_starts.partition_point(|&start| start <= offset) - 1) as u32
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = self.floor_char_boundary(offset.min(self.text.len()));
+        let line = self.line(offset);
+        let line_start = self.line_starts[line as usize];
+        let character = self.text[line_start..offset]
+            .chars()
+            .map(|c| c.len_utf16() as u32)
+            .sum();
+        Position::new(line, character)
+    }
+
+    pub fn range(&self, start: usize, end: usize) -> Range {
+        Range::new(self.position(start), self.position(end))
+    }
+
+    /// 文档末尾位置
+    pub fn end(&self) -> Position {
+        self.position(self.text.len())
+    }
+
+    /// 第 `line` 行去除首尾空白后的区间，行不存在时返回文档末尾
+    pub fn line_range(&self, line: u32) -> Range {
+        let Some(&start) = self.line_starts.get(line as usize) else {
+            return Range::new(self.end(), self.end());
+        };
+        let content = self.text[start..].lines().next().unwrap_or("");
+        let trimmed = content.trim_start();
+        let start = start + (content.len() - trimmed.len());
+        self.range(start, start + trimmed.trim_end().len())
+    }
+
+    fn floor_char_boundary(&self, mut offset: usize) -> usize {
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+/// 使用引擎解析规则，失败时返回带源码位置的诊断
+pub fn check(lang: Lang, text: &str) -> Result<(), Diagnostic> {
+    match lang {
+        Lang::Wpl => {
+            let code = WplCode::build(PathBuf::from(""), text)
+                .map_err(|e| Diagnostic::syntax(WPL_SYNTAX, text, e))?;
+            code.parse_pkg()
+                .map_err(|e| Diagnostic::syntax(WPL_SYNTAX, text, e))?;
+        }
+        Lang::Oml => {
+            let filter_oml = strip_comments(text);
+            oml_parse(&mut filter_oml.as_str(), "")
+                .map_err(|e| Diagnostic::syntax(OML_SYNTAX, text, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 解析规则并生成 LSP 诊断
+pub fn diagnostics(lang: Lang, text: &str) -> Vec<lsp_types::Diagnostic> {
+    match check(lang, text) {
+        Ok(()) => Vec::new(),
+        Err(diagnostic) => vec![to_lsp(text, &diagnostic)],
+    }
+}
+
+/// 转换为 LSP 诊断；引擎未给出位置时标注在首个非空行
+pub fn to_lsp(text: &str, diagnostic: &Diagnostic) -> lsp_types::Diagnostic {
+    let index = LineIndex::new(text);
+    let range = diagnostic
+        .range
+        .and_then(|range| {
+            let start = offset_of(text, range.start)?;
+            let end = offset_of(text, range.end)?;
+            Some(index.range(start, end))
+        })
+        .unwrap_or_else(|| {
+            let line = text
+                .lines()
+                .position(|line| !line.trim().is_empty())
+                .unwrap_or(0);
+            index.line_range(line as u32)
+        });
+    let severity = match diagnostic.severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Info => DiagnosticSeverity::INFORMATION,
+    };
+
+    lsp_types::Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(diagnostic.code.to_string())),
+        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        message: diagnostic.message.clone(),
+        ..Default::default()
+    }
+}
+
+/// 格式化整篇文档。
+///
+/// 存在语法错误时返回 None，避免格式化器改写无法解析的内容；已格式化时返回空列表。
+pub fn formatting(lang: Lang, text: &str) -> Option<Vec<TextEdit>> {
+    let formatted = match lang {
+        Lang::Wpl => WplFormatter::new().format(text),
+        Lang::Oml => OmlFormatter::new().format(text),
+    }
+    .ok()?;
+    if formatted == text {
+        return Some(Vec::new());
+    }
+
+    let index = LineIndex::new(text);
+    Some(vec![TextEdit::new(
+        Range::new(Position::new(0, 0), index.end()),
+        formatted,
+    )])
+}
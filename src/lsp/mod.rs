@@ -0,0 +1,172 @@
+// WPL/OML 语言服务器：通过 stdio 提供诊断、格式化、文档符号与折叠区间
+
+pub mod analysis;
+pub mod symbols;
+
+pub use analysis::{Lang, LineIndex};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest, FoldingRangeRequest, Formatting, Request as LspRequest,
+};
+use lsp_types::{
+    DocumentSymbolResponse, FoldingRangeProviderCapability, InitializeResult, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Uri,
+};
+use std::collections::HashMap;
+use std::error::Error;
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// 已打开的文档
+struct Document {
+    lang: Lang,
+    text: String,
+    version: i32,
+}
+
+/// 启动 stdio 语言服务器，直到客户端发送 shutdown/exit
+pub fn run() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let (id, _params) = connection.initialize_start()?;
+    let result = InitializeResult {
+        capabilities: capabilities(),
+        server_info: Some(ServerInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    };
+    connection.initialize_finish(id, serde_json::to_value(result)?)?;
+
+    Server::default().main_loop(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        ..Default::default()
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Uri, Document>,
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> LspResult<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    connection.sender.send(self.handle_request(req).into())?;
+                }
+                Message::Notification(not) => {
+                    if let Some(params) = self.handle_notification(not) {
+                        let not = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                        connection.sender.send(not.into())?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, req: Request) -> Response {
+        match req.method.as_str() {
+            Formatting::METHOD => respond::<Formatting>(req, |params| {
+                let doc = self.documents.get(&params.text_document.uri)?;
+                analysis::formatting(doc.lang, &doc.text)
+            }),
+            DocumentSymbolRequest::METHOD => respond::<DocumentSymbolRequest>(req, |params| {
+                let doc = self.documents.get(&params.text_document.uri)?;
+                let symbols = symbols::document_symbols(doc.lang, &doc.text);
+                Some(DocumentSymbolResponse::Nested(symbols))
+            }),
+            FoldingRangeRequest::METHOD => respond::<FoldingRangeRequest>(req, |params| {
+                let doc = self.documents.get(&params.text_document.uri)?;
+                Some(symbols::folding_ranges(doc.lang, &doc.text))
+            }),
+            _ => Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("不支持的请求: {}", req.method),
+            ),
+        }
+    }
+
+    /// 处理文档同步通知，返回需要发布的诊断
+    fn handle_notification(&mut self, not: Notification) -> Option<PublishDiagnosticsParams> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification_params::<DidOpenTextDocument>(not)?;
+                let item = params.text_document;
+                let lang = Lang::from_language_id(&item.language_id)
+                    .or_else(|| Lang::from_uri(&item.uri))?;
+                let doc = Document {
+                    lang,
+                    text: item.text,
+                    version: item.version,
+                };
+                Some(self.update(item.uri, doc))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification_params::<DidChangeTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                // 全量同步：最后一次变更即为完整内容
+                let text = params.content_changes.into_iter().last()?.text;
+                let lang = self.documents.get(&uri)?.lang;
+                let doc = Document {
+                    lang,
+                    text,
+                    version: params.text_document.version,
+                };
+                Some(self.update(uri, doc))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = notification_params::<DidCloseTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri)?;
+                // 关闭时清空该文档的诊断
+                Some(PublishDiagnosticsParams::new(uri, Vec::new(), None))
+            }
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, uri: Uri, doc: Document) -> PublishDiagnosticsParams {
+        let diagnostics = analysis::diagnostics(doc.lang, &doc.text);
+        let version = Some(doc.version);
+        self.documents.insert(uri.clone(), doc);
+        PublishDiagnosticsParams::new(uri, diagnostics, version)
+    }
+}
+
+/// 反序列化请求参数并执行处理函数，参数非法时返回 InvalidParams
+fn respond<R: LspRequest>(req: Request, handler: impl FnOnce(R::Params) -> R::Result) -> Response {
+    match serde_json::from_value::<R::Params>(req.params) {
+        Ok(params) => Response::new_ok(req.id, handler(params)),
+        Err(e) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+
+fn notification_params<N: LspNotification>(not: Notification) -> Option<N::Params> {
+    serde_json::from_value(not.params)
+        .inspect_err(|e| warn!("通知 {} 参数无效: {}", not.method, e))
+        .ok()
+}
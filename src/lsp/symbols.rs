@@ -0,0 +1,321 @@
+// 文档符号与折叠区间：基于轻量词法扫描，规则存在语法错误时也尽量给出结果
+
+use super::analysis::{Lang, LineIndex};
+use crate::utils::oml::{at_word_start, quoted_len, raw_func_len, raw_string_len};
+use crate::utils::{oml_formatter, wpl_formatter};
+use lsp_types::{DocumentSymbol, FoldingRange, FoldingRangeKind, Range, SymbolKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    /// 标识符、路径（如 `/example/simple`）或数字
+    Word,
+    /// 字符串、原始字符串或原样函数块，内部内容不参与结构分析
+    Literal,
+    Comment,
+    Punct(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+/// 文档中的 WPL 包/规则或 OML 目标字段
+pub fn document_symbols(lang: Lang, text: &str) -> Vec<DocumentSymbol> {
+    let index = LineIndex::new(text);
+    let tokens: Vec<Token> = tokenize(lang, text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    match lang {
+        Lang::Wpl => wpl_symbols(text, &tokens, &index),
+        Lang::Oml => oml_symbols(text, &tokens, &index),
+    }
+}
+
+/// 跨行的括号块与块注释
+pub fn folding_ranges(lang: Lang, text: &str) -> Vec<FoldingRange> {
+    let index = LineIndex::new(text);
+    let mut ranges = Vec::new();
+    let mut open = Vec::new();
+
+    for token in tokenize(lang, text) {
+        match token.kind {
+            TokenKind::Punct('{' | '(' | '[') => open.push(token.start),
+            TokenKind::Punct('}' | ')' | ']') => {
+                let Some(start) = open.pop() else {
+                    continue;
+                };
+                // 折叠到闭合括号的上一行，保留闭合括号可见
+                let (start_line, end_line) = (index.line(start), index.line(token.start));
+                if end_line > start_line + 1 {
+                    ranges.push(folding_range(start_line, end_line - 1, None));
+                }
+            }
+            TokenKind::Comment => {
+                let (start_line, end_line) = (index.line(token.start), index.line(token.end));
+                if end_line > start_line {
+                    let kind = Some(FoldingRangeKind::Comment);
+                    ranges.push(folding_range(start_line, end_line, kind));
+                }
+            }
+            _ => {}
+        }
+    }
+    ranges.sort_by_key(|range| (range.start_line, range.end_line));
+    ranges
+}
+
+fn folding_range(start_line: u32, end_line: u32, kind: Option<FoldingRangeKind>) -> FoldingRange {
+    FoldingRange {
+        start_line,
+        end_line,
+        kind,
+        ..Default::default()
+    }
+}
+
+/// `package <name> { rule <name> { ... } }`，规则嵌套在包内
+fn wpl_symbols(text: &str, tokens: &[Token], index: &LineIndex) -> Vec<DocumentSymbol> {
+    // 每个 `{` 对应一帧，属于包/规则的帧携带待完成的符号
+    let mut frames: Vec<Option<DocumentSymbol>> = Vec::new();
+    let mut roots = Vec::new();
+    let mut pending: Option<(SymbolKind, usize, Range, String)> = None;
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let token = tokens[idx];
+        match token.kind {
+            TokenKind::Word if pending.is_none() => {
+                let kind = match &text[token.start..token.end] {
+                    "package" => Some(SymbolKind::PACKAGE),
+                    "rule" => Some(SymbolKind::FUNCTION),
+                    _ => None,
+                };
+                let name_end = tokens[idx + 1..]
+                    .iter()
+                    .position(|t| t.kind == TokenKind::Punct('{'))
+                    .map(|pos| idx + 1 + pos);
+                // 名称只能由词组成，避免把规则体中名为 rule 的字段误认作声明
+                if let (Some(kind), Some(name_end)) = (kind, name_end)
+                    && name_end > idx + 1
+                    && tokens[idx + 1..name_end]
+                        .iter()
+                        .all(|t| t.kind == TokenKind::Word)
+                {
+                    let (first, last) = (tokens[idx + 1], tokens[name_end - 1]);
+                    let name = text[first.start..last.end].to_string();
+                    let selection = index.range(first.start, last.end);
+                    pending = Some((kind, token.start, selection, name));
+                    idx = name_end;
+                    continue;
+                }
+            }
+            TokenKind::Punct('{') => {
+                let symbol = pending.take().map(|(kind, start, selection, name)| {
+                    new_symbol(name, kind, index.range(start, token.end), selection)
+                });
+                frames.push(symbol);
+            }
+            TokenKind::Punct('}') => {
+                if let Some(Some(mut symbol)) = frames.pop() {
+                    symbol.range.end = index.position(token.end);
+                    attach(&mut frames, &mut roots, symbol);
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    // 未闭合的块延伸到文档末尾
+    while let Some(frame) = frames.pop() {
+        if let Some(mut symbol) = frame {
+            symbol.range.end = index.end();
+            attach(&mut frames, &mut roots, symbol);
+        }
+    }
+    roots
+}
+
+/// 将完成的符号挂到最近的外层符号下，没有外层时作为顶层符号
+fn attach(
+    frames: &mut [Option<DocumentSymbol>],
+    roots: &mut Vec<DocumentSymbol>,
+    symbol: DocumentSymbol,
+) {
+    match frames.iter_mut().rev().find_map(Option::as_mut) {
+        Some(parent) => parent.children.get_or_insert_with(Vec::new).push(symbol),
+        None => roots.push(symbol),
+    }
+}
+
+/// OML 头部 `name : <model>` 作为顶层符号，`---` 之后的顶层赋值目标作为字段
+fn oml_symbols(text: &str, tokens: &[Token], index: &LineIndex) -> Vec<DocumentSymbol> {
+    let separator = tokens
+        .iter()
+        .position(|t| t.kind == TokenKind::Word && &text[t.start..t.end] == "---");
+    let body = separator.map_or(tokens, |pos| &tokens[pos + 1..]);
+    let fields = oml_fields(text, body, index);
+
+    let model = separator.and_then(|sep| {
+        let header = &tokens[..sep];
+        header.windows(3).find_map(|window| match window {
+            [key, colon, name]
+                if &text[key.start..key.end] == "name"
+                    && colon.kind == TokenKind::Punct(':')
+                    && name.kind == TokenKind::Word =>
+            {
+                Some(*name)
+            }
+            _ => None,
+        })
+    });
+    let Some(name) = model else {
+        return fields;
+    };
+
+    let mut symbol = new_symbol(
+        text[name.start..name.end].to_string(),
+        SymbolKind::MODULE,
+        index.range(0, text.len()),
+        index.range(name.start, name.end),
+    );
+    symbol.children = Some(fields);
+    vec![symbol]
+}
+
+/// 顶层语句 `target[: type] = expr;` 中的目标字段，`match` 等块语句可省略结尾分号
+fn oml_fields(text: &str, tokens: &[Token], index: &LineIndex) -> Vec<DocumentSymbol> {
+    let mut fields = Vec::new();
+    let mut depth = 0usize;
+    let mut stmt_start = 0usize;
+    let mut current: Option<DocumentSymbol> = None;
+
+    for (idx, token) in tokens.iter().enumerate() {
+        let next = tokens.get(idx + 1);
+        match token.kind {
+            TokenKind::Punct('{' | '(' | '[') => depth += 1,
+            TokenKind::Punct(close @ ('}' | ')' | ']')) => {
+                depth = depth.saturating_sub(1);
+                let ends_block = close == '}'
+                    && depth == 0
+                    && next.is_none_or(|t| t.kind != TokenKind::Punct(';'));
+                if ends_block {
+                    finish_field(&mut current, &mut fields, index, token.end);
+                    stmt_start = idx + 1;
+                }
+            }
+            TokenKind::Punct(';') if depth == 0 => {
+                finish_field(&mut current, &mut fields, index, token.end);
+                stmt_start = idx + 1;
+            }
+            TokenKind::Punct('=') if depth == 0 && current.is_none() => {
+                // 跳过 `==`、`=>` 等复合运算符
+                let compound = next.is_some_and(|t| {
+                    t.start == token.end && matches!(t.kind, TokenKind::Punct('=' | '>'))
+                });
+                if !compound && idx > stmt_start {
+                    current = Some(target_symbol(text, &tokens[stmt_start..idx], index));
+                }
+            }
+            _ => {}
+        }
+    }
+    finish_field(&mut current, &mut fields, index, text.len());
+    fields
+}
+
+/// 由赋值号左侧的记号生成字段符号，`name: type` 中的类型作为详情
+fn target_symbol(text: &str, target: &[Token], index: &LineIndex) -> DocumentSymbol {
+    let (first, last) = (target[0], target[target.len() - 1]);
+    let colon = target
+        .iter()
+        .position(|t| t.kind == TokenKind::Punct(':'))
+        .filter(|&pos| pos > 0);
+    let name_end = colon.map_or(last.end, |pos| target[pos - 1].end);
+
+    let mut symbol = new_symbol(
+        text[first.start..name_end].to_string(),
+        SymbolKind::FIELD,
+        index.range(first.start, last.end),
+        index.range(first.start, name_end),
+    );
+    symbol.detail = colon
+        .and_then(|pos| target.get(pos + 1))
+        .map(|ty| text[ty.start..last.end].to_string());
+    symbol
+}
+
+fn finish_field(
+    current: &mut Option<DocumentSymbol>,
+    fields: &mut Vec<DocumentSymbol>,
+    index: &LineIndex,
+    end: usize,
+) {
+    if let Some(mut symbol) = current.take() {
+        symbol.range.end = index.position(end);
+        fields.push(symbol);
+    }
+}
+
+#[allow(deprecated)]
+fn new_symbol(name: String, kind: SymbolKind, range: Range, selection: Range) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail: None,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: selection,
+        children: None,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '/' | '.' | '-')
+}
+
+/// 切分记号：识别注释、字符串、原始字符串与各语言的原样函数块
+fn tokenize(lang: Lang, text: &str) -> Vec<Token> {
+    let raw_func_len = |rest: &str| match lang {
+        Lang::Wpl => wpl_formatter::raw_call_len(rest),
+        Lang::Oml => raw_func_len(rest, oml_formatter::RAW_FUNCS),
+    };
+
+    let mut tokens = Vec::new();
+    let mut pos = 0usize;
+    while let Some(c) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+        let (kind, len) = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if rest.starts_with("//") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            let len = body.find("*/").map_or(rest.len(), |end| end + 4);
+            (TokenKind::Comment, len)
+        } else if c == '"' {
+            (TokenKind::Literal, quoted_len(rest))
+        } else if at_word_start(text, pos)
+            && let Some(len) = raw_string_len(rest).or_else(|| raw_func_len(rest))
+        {
+            (TokenKind::Literal, len)
+        } else if is_word_char(c) {
+            let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+            (TokenKind::Word, len)
+        } else {
+            (TokenKind::Punct(c), c.len_utf8())
+        };
+        tokens.push(Token {
+            kind,
+            start: pos,
+            end: pos + len,
+        });
+        pos += len;
+    }
+    tokens
+}
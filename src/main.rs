@@ -1,14 +1,19 @@
 use clap::Parser;
+use wp_editor::cli::{self, Args, Command};
 use wp_editor::server::start;
+#[cfg(feature = "perf-alloc")]
+use wp_editor::utils::perf::CountingAllocator;
 
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    // 可以在这里添加其他命令行参数
-}
+// 统计内存分配，供性能测试接口输出分配信息；仅在启用 perf-alloc 特性时注册
+#[cfg(feature = "perf-alloc")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
 
 #[tokio::main]
 async fn main() {
-    Args::parse();
-    start().await.expect("启动服务器失败");
+    let args = Args::parse();
+    match args.command.unwrap_or(Command::Serve) {
+        Command::Serve => start().await.expect("启动服务器失败"),
+        command => std::process::exit(cli::run(command).await),
+    }
 }
@@ -1,6 +1,8 @@
 // 应用启动逻辑
 
-use crate::{api, server::Setting};
+use crate::api;
+use crate::db::DbPool;
+use crate::server::{Setting, knowledge};
 use actix_web::{App, HttpRequest, HttpResponse, HttpServer, Result, middleware::Logger, web};
 use mime_guess::from_path;
 use rust_embed::RustEmbed;
@@ -37,6 +39,39 @@ async fn static_files(req: HttpRequest) -> Result<HttpResponse> {
     }
 }
 
+/// 按 `[database]` 配置创建连接池并做连通性检查，失败时仅告警
+async fn init_db_pool(setting: &Setting) -> Option<DbPool> {
+    let Some(conf) = &setting.database else {
+        warn!("未配置 [database]，知识库相关功能不可用");
+        return None;
+    };
+
+    let pool = match DbPool::new(&conf.url, conf.max_connections, conf.min_connections).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            warn!("连接知识库数据库失败，知识库相关功能不可用: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = pool.test_connection().await {
+        warn!("知识库数据库健康检查失败，知识库相关功能不可用: {}", e);
+        return None;
+    }
+    if conf.auto_migrate {
+        match pool.migrate().await {
+            Ok(applied) if !applied.is_empty() => info!("数据库迁移完成: {:?}", applied),
+            Ok(_) => {}
+            Err(e) => {
+                warn!("数据库迁移失败，知识库相关功能不可用: {}", e);
+                return None;
+            }
+        }
+    }
+
+    info!("知识库数据库连接成功");
+    Some(pool)
+}
+
 pub async fn start() -> std::io::Result<()> {
     let setting = Setting::load();
     simple_log::quick!(&setting.log.level);
@@ -48,8 +83,20 @@ pub async fn start() -> std::io::Result<()> {
     let shared_record: SharedRecord = Arc::new(Mutex::new(None));
     let shared_record_data = web::Data::new(shared_record);
 
+    // 知识库数据库：未配置或连接失败时不注入连接池，相关接口返回 503
+    let db_pool = init_db_pool(&setting).await.map(web::Data::new);
+    if let Some(pool) = &db_pool
+        && let Err(e) = knowledge::reload_knowledge(pool).await
+    {
+        warn!("加载知识库失败，OML 查询将无法命中知识库数据: {}", e);
+    }
+
     HttpServer::new(move || {
-        App::new()
+        let mut app = App::new();
+        if let Some(pool) = &db_pool {
+            app = app.app_data(pool.clone());
+        }
+        app
             // HTTP 请求访问日志：使用默认格式，并排除常见前端路由和静态资源，只保留 /api/... 日志
             .wrap(
                 Logger::default()
@@ -64,9 +111,28 @@ pub async fn start() -> std::io::Result<()> {
             .service(api::debug_parse)
             .service(api::debug_transform)
             .service(api::debug::debug_examples)
+            .service(api::debug::performance_run)
+            .service(api::debug::debug_cases_run)
+            .service(api::debug::debug_knowledge_query)
             .service(api::wpl_format)
             .service(api::oml_format)
+            .service(api::wpl_format_check)
+            .service(api::oml_format_check)
             .service(api::decode_base64)
+            // 知识库 API
+            .service(api::knowledge::get_db_list)
+            .service(api::knowledge::query)
+            .service(api::knowledge::list_configs)
+            .service(api::knowledge::get_config)
+            .service(api::knowledge::create_config)
+            .service(api::knowledge::update_config)
+            .service(api::knowledge::update_config_active)
+            .service(api::knowledge::delete_config)
+            .service(api::knowledge::upload_data)
+            .service(api::knowledge::list_revisions)
+            .service(api::knowledge::knowledge_schema)
+            .service(api::knowledge::diff_revisions)
+            .service(api::knowledge::rollback_config)
             // 默认路由：未匹配的 /api/* 返回 JSON 404，其余走静态文件（前端 SPA）
             .default_service(web::to(|req: HttpRequest| async move {
                 if req.path().starts_with("/api/") {
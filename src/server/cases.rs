@@ -0,0 +1,400 @@
+use crate::server::examples;
+use crate::utils::{
+    EventIdMode, ParseOptions, ParseOutcome, ParsedField, convert_record, record_to_fields,
+    warp_check_outcome,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wp_lang::WplCode;
+use wp_specs::WildArray;
+
+/// 规则回归用例文件名，与 `.wpl` 文件放在同一目录
+pub const CASE_FILE_NAME: &str = "cases.toml";
+
+/// `cases.toml` 文件结构
+///
+/// ```toml
+/// [[case]]
+/// name = "nginx access"
+/// input = '''222.133.52.20 - - ...'''
+///
+/// [case.fields]        # 期望的 WPL 解析字段，只比较列出的字段
+/// sip = "222.133.52.20"
+///
+/// [case.oml]           # 可选：期望的 OML 转换输出
+/// src_ip = "222.133.52.20"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CaseFile {
+    #[serde(default, rename = "case")]
+    pub cases: Vec<RuleCase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleCase {
+    pub name: String,
+    pub input: String,
+    /// 指定使用的 WPL 文件名，目录中只有一个 `.wpl` 时可省略
+    #[serde(default)]
+    pub wpl: Option<String>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default)]
+    pub oml: Option<BTreeMap<String, String>>,
+    /// 期望解析失败
+    #[serde(default)]
+    pub expect_error: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStage {
+    Wpl,
+    Oml,
+}
+
+/// 字段级差异：`actual` 为空表示输出中缺少该字段
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDiff {
+    pub stage: CaseStage,
+    pub name: String,
+    pub expected: String,
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub file: String,
+    pub name: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diffs: Vec<FieldDiff>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CaseResult {
+    fn new(name: &str) -> Self {
+        Self {
+            file: String::new(),
+            name: name.to_string(),
+            passed: false,
+            rule: None,
+            diffs: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn failed(name: &str, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(name)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaseReport {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<CaseResult>,
+}
+
+impl CaseReport {
+    fn push(&mut self, result: CaseResult) {
+        self.total += 1;
+        if result.passed {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.results.push(result);
+    }
+}
+
+/// 运行规则仓库中全部 `cases.toml` 用例。
+///
+/// WPL 仓库路径不存在或不是目录时返回错误，避免路径写错时静默跑零条用例。
+pub fn run_cases(
+    wpl_path: PathBuf,
+    oml_path: PathBuf,
+) -> Result<CaseReport, Box<dyn std::error::Error>> {
+    if !wpl_path.is_dir() {
+        return Err(format!("WPL 规则仓库不存在或不是目录: {}", wpl_path.display()).into());
+    }
+    let oml_examples = if oml_path.exists() {
+        examples::oml_examples(oml_path)?
+    } else {
+        Vec::new()
+    };
+    let mut report = CaseReport::default();
+    collect_cases(&wpl_path, &oml_examples, &mut report)?;
+    Ok(report)
+}
+
+fn collect_cases(
+    dir: &Path,
+    oml_examples: &[(WildArray, String)],
+    report: &mut CaseReport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let case_path = dir.join(CASE_FILE_NAME);
+    if case_path.is_file() {
+        run_case_file(&case_path, oml_examples, report)?;
+    }
+
+    let mut sub_dirs: Vec<PathBuf> = dir
+        .read_dir()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    sub_dirs.sort();
+    for sub_dir in sub_dirs {
+        collect_cases(&sub_dir, oml_examples, report)?;
+    }
+    Ok(())
+}
+
+fn run_case_file(
+    case_path: &Path,
+    oml_examples: &[(WildArray, String)],
+    report: &mut CaseReport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = case_path.display().to_string();
+    let content = fs::read_to_string(case_path)?;
+    let case_file: CaseFile = match toml::from_str(&content) {
+        Ok(v) => v,
+        Err(e) => {
+            let mut result = CaseResult::failed(CASE_FILE_NAME, format!("用例文件解析失败: {e}"));
+            result.file = file;
+            report.push(result);
+            return Ok(());
+        }
+    };
+
+    let dir = case_path.parent().unwrap_or(Path::new("."));
+    for case in case_file.cases {
+        let mut result = resolve_wpl(dir, case.wpl.as_deref())
+            .and_then(|wpl_path| run_case(&wpl_path, &case, oml_examples))
+            .unwrap_or_else(|e| CaseResult::failed(&case.name, e));
+        result.file = file.clone();
+        report.push(result);
+    }
+    Ok(())
+}
+
+/// 定位用例使用的 WPL 文件
+fn resolve_wpl(dir: &Path, wpl: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(name) = wpl {
+        let path = dir.join(name);
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!("WPL 文件不存在: {}", path.display()))
+        };
+    }
+    let mut wpl_files: Vec<PathBuf> = dir
+        .read_dir()
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("wpl"))
+        .collect();
+    wpl_files.sort();
+    match wpl_files.len() {
+        0 => Err(format!("目录 {} 中没有 WPL 文件", dir.display())),
+        1 => Ok(wpl_files.remove(0)),
+        _ => Err(format!(
+            "目录 {} 中有多个 WPL 文件，请在用例中通过 wpl 字段指定",
+            dir.display()
+        )),
+    }
+}
+
+fn run_case(
+    wpl_path: &Path,
+    case: &RuleCase,
+    oml_examples: &[(WildArray, String)],
+) -> Result<CaseResult, String> {
+    let wpl = fs::read_to_string(wpl_path).map_err(|e| e.to_string())?;
+    // 固定输出：不追加随机事件 ID，保证多次运行结果一致
+    let options = ParseOptions {
+        event_id: EventIdMode::Omit,
+        ..ParseOptions::default()
+    };
+
+    let mut result = CaseResult::new(&case.name);
+
+    let ParseOutcome { rule, record, .. } = match warp_check_outcome(&wpl, &case.input, &options) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            result.passed = case.expect_error;
+            if !case.expect_error {
+                result.error = Some(e.to_string());
+            }
+            return Ok(result);
+        }
+    };
+    result.rule = Some(rule.clone());
+    if case.expect_error {
+        result.error = Some("期望解析失败，但解析成功".to_string());
+        return Ok(result);
+    }
+
+    let fields = record_to_fields(&record);
+    result.diffs = diff_fields(CaseStage::Wpl, &case.fields, &fields);
+
+    if let Some(expected_oml) = &case.oml {
+        let wpl_name = format!("{}/{}", package_name(wpl_path, &wpl)?, rule);
+        let Some((_, oml_code)) = oml_examples
+            .iter()
+            .find(|(rules, _)| rules.0.iter().any(|r| r.matches(&wpl_name)))
+        else {
+            result.error = Some(format!("未找到与规则 {wpl_name} 关联的 OML"));
+            return Ok(result);
+        };
+        match convert_record(oml_code, record) {
+            Ok(transformed) => {
+                let oml_fields = record_to_fields(&transformed);
+                result
+                    .diffs
+                    .extend(diff_fields(CaseStage::Oml, expected_oml, &oml_fields));
+            }
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return Ok(result);
+            }
+        }
+    }
+
+    result.passed = result.diffs.is_empty();
+    Ok(result)
+}
+
+fn package_name(wpl_path: &Path, wpl: &str) -> Result<String, String> {
+    let code = WplCode::build(wpl_path.to_path_buf(), wpl).map_err(|e| e.to_string())?;
+    let pkg = code.parse_pkg().map_err(|e| e.to_string())?;
+    let pkg_name = pkg.name().to_string();
+    let pkg_name = pkg_name.trim();
+    Ok(pkg_name.strip_suffix('/').unwrap_or(pkg_name).to_string())
+}
+
+/// 对比期望字段与实际输出，只比较期望中列出的字段
+fn diff_fields(
+    stage: CaseStage,
+    expected: &BTreeMap<String, String>,
+    actual: &[ParsedField],
+) -> Vec<FieldDiff> {
+    expected
+        .iter()
+        .filter_map(|(name, expected_value)| {
+            let actual_value = actual
+                .iter()
+                .find(|f| &f.name == name)
+                .map(|f| f.value.clone());
+            if actual_value.as_ref() == Some(expected_value) {
+                return None;
+            }
+            Some(FieldDiff {
+                stage,
+                name: name.clone(),
+                expected: expected_value.clone(),
+                actual: actual_value,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const NGINX_WPL: &str = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    const NGINX_OML: &str = r#"name : /oml/example/simple
+
+rule :
+    /example/simple*
+---
+src_ip = take(option:[sip]) ;"#;
+
+    const NGINX_CASES: &str = r#"
+[[case]]
+name = "nginx access"
+input = '''222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0" "-"'''
+
+[case.fields]
+sip = "222.133.52.20"
+"http/status" = "200"
+
+[case.oml]
+src_ip = "222.133.52.20"
+
+[[case]]
+name = "wrong expectation"
+input = '''10.0.0.1 - - [06/Aug/2019:12:12:19 +0800] "GET / HTTP/1.1" 404 0 "-" "curl" "-"'''
+
+[case.fields]
+"http/status" = "200"
+
+[[case]]
+name = "garbage"
+input = "not a log"
+expect_error = true
+"#;
+
+    fn setup_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let wpl_dir = temp_dir.path().join("wpl/nginx");
+        let oml_dir = temp_dir.path().join("oml/nginx");
+        fs::create_dir_all(&wpl_dir).unwrap();
+        fs::create_dir_all(&oml_dir).unwrap();
+        fs::write(wpl_dir.join("parse.wpl"), NGINX_WPL).unwrap();
+        fs::write(wpl_dir.join(CASE_FILE_NAME), NGINX_CASES).unwrap();
+        fs::write(oml_dir.join("adm.oml"), NGINX_OML).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_run_cases_reports_field_diffs() {
+        let temp_dir = setup_repo();
+        let report = run_cases(temp_dir.path().join("wpl"), temp_dir.path().join("oml")).unwrap();
+
+        assert_eq!(report.total, 3);
+        assert_eq!(report.passed, 2, "{:?}", report.results);
+        assert_eq!(report.failed, 1);
+
+        let failed = report.results.iter().find(|r| !r.passed).unwrap();
+        assert_eq!(failed.name, "wrong expectation");
+        assert_eq!(failed.diffs.len(), 1);
+        assert_eq!(failed.diffs[0].stage, CaseStage::Wpl);
+        assert_eq!(failed.diffs[0].expected, "200");
+        assert_eq!(failed.diffs[0].actual.as_deref(), Some("404"));
+    }
+
+    #[test]
+    fn test_run_cases_reports_invalid_case_file() {
+        let temp_dir = setup_repo();
+        let wpl_dir = temp_dir.path().join("wpl/nginx");
+        fs::write(wpl_dir.join(CASE_FILE_NAME), "[[case]]\nname = 1").unwrap();
+
+        let report = run_cases(temp_dir.path().join("wpl"), temp_dir.path().join("oml")).unwrap();
+        assert_eq!(report.failed, 1);
+        assert!(report.results[0].error.is_some());
+    }
+
+    #[test]
+    fn test_run_cases_rejects_missing_wpl_repo() {
+        let temp_dir = setup_repo();
+        let result = run_cases(temp_dir.path().join("wlp"), temp_dir.path().join("oml"));
+        assert!(result.is_err(), "WPL 仓库路径错误时不应返回空报告");
+    }
+}
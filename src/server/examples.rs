@@ -28,7 +28,14 @@ pub fn wpl_examples(
         // 获取原始的wpl代码
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
-        example.wpl_code = wpl_formatter.format_content(&contents);
+        example.wpl_code = wpl_formatter.format(&contents).unwrap_or_else(|e| {
+            warn!(
+                "格式化 WPL 示例 {} 失败，使用原文: {}",
+                wpl_path.display(),
+                e
+            );
+            contents.clone()
+        });
         // 获取日志示例数据
         let sample_data_dir = wpl_path.parent().unwrap().join("sample.dat");
         let mut sample_data = String::new();
@@ -65,7 +72,9 @@ pub fn wpl_examples(
     wpl_path.read_dir()?.for_each(|entry| {
         if let Ok(entry) = entry {
             let path = entry.path();
-            let _ = wpl_examples(path, oml_examples, examples);
+            if let Err(e) = wpl_examples(path.clone(), oml_examples, examples) {
+                warn!("加载 WPL 示例 {} 失败: {}", path.display(), e);
+            }
         }
     });
     Ok(())
@@ -0,0 +1,30 @@
+// 知识库同步：将数据库中激活的配置物化并加载到调试引擎
+
+use crate::db::{DbPool, KnowledgeConfigRepo};
+use crate::error::AppError;
+use crate::server::Setting;
+use crate::utils::knowledge::{load_knowledge, materialize_configs};
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+// 物化目录与引擎全局共享，同一时刻只允许一次重建
+static RELOAD_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// 从数据库读取全部配置，重建知识库目录并重新加载，返回加载的配置数量
+pub async fn reload_knowledge(pool: &DbPool) -> Result<usize, AppError> {
+    let _guard = RELOAD_LOCK.lock().await;
+
+    let configs = KnowledgeConfigRepo::new(pool.inner()).find_all().await?;
+    let work_dir = PathBuf::from(Setting::load().knowledge.work_dir);
+
+    let count = tokio::task::spawn_blocking(move || {
+        let count = materialize_configs(&work_dir, &configs)?;
+        load_knowledge(&work_dir.to_string_lossy()).map_err(AppError::internal)?;
+        Ok::<_, AppError>(count)
+    })
+    .await
+    .map_err(AppError::internal)??;
+
+    info!("知识库加载完成，共 {} 个激活配置", count);
+    Ok(count)
+}
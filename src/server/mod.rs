@@ -1,8 +1,10 @@
 // 服务器模块
 
 pub mod app;
+pub mod cases;
 pub mod examples;
+pub mod knowledge;
 pub mod setting;
 
 pub use app::start;
-pub use setting::{LogConf, Setting, WebConf};
+pub use setting::{DatabaseConf, KnowledgeConf, LogConf, Setting, WebConf};
@@ -50,11 +50,76 @@ impl Default for RepoConf {
     }
 }
 
+/// 知识库配置所用数据库，未配置时知识库相关功能不可用
+#[derive(Debug, Deserialize, Clone)]
+pub struct DatabaseConf {
+    pub url: String,
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+    #[serde(default = "default_min_connections")]
+    pub min_connections: u32,
+    /// 启动时自动执行数据库迁移；关闭后需通过 `wp-editor migrate` 手动执行
+    #[serde(default = "default_auto_migrate")]
+    pub auto_migrate: bool,
+}
+
+/// 知识库运行配置
+#[derive(Debug, Deserialize, Clone)]
+pub struct KnowledgeConf {
+    /// 激活的知识库配置物化到该目录后加载到调试引擎
+    #[serde(default = "default_knowledge_work_dir")]
+    pub work_dir: String,
+    /// 单条知识库查询的最长执行时间（毫秒）
+    #[serde(default = "default_query_timeout_ms")]
+    pub query_timeout_ms: u64,
+    /// 单条知识库查询最多返回的行数，超出部分截断
+    #[serde(default = "default_max_rows")]
+    pub max_rows: usize,
+}
+
+impl Default for KnowledgeConf {
+    fn default() -> Self {
+        KnowledgeConf {
+            work_dir: default_knowledge_work_dir(),
+            query_timeout_ms: default_query_timeout_ms(),
+            max_rows: default_max_rows(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct Setting {
     pub log: LogConf,
     pub web: WebConf,
     pub repo: RepoConf,
+    #[serde(default)]
+    pub database: Option<DatabaseConf>,
+    #[serde(default)]
+    pub knowledge: KnowledgeConf,
+}
+
+fn default_knowledge_work_dir() -> String {
+    "./.run/knowledge".to_string()
+}
+
+fn default_query_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_max_rows() -> usize {
+    1000
+}
+
+fn default_max_connections() -> u32 {
+    10
+}
+
+fn default_min_connections() -> u32 {
+    1
+}
+
+fn default_auto_migrate() -> bool {
+    true
 }
 
 fn default_oml_rule_repo() -> String {
@@ -0,0 +1,207 @@
+// 知识库 CSV 导入：解压、按建表 SQL 校验列并规范化为可落库的 CSV 文本
+
+use crate::error::AppError;
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::io::Read;
+
+/// 上传文件（解压后）允许的最大字节数
+pub const MAX_UPLOAD_BYTES: usize = 64 * 1024 * 1024;
+/// 默认预览行数
+pub const DEFAULT_PREVIEW_ROWS: usize = 20;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 建表 SQL 中不是列定义的约束关键字
+const CONSTRAINT_KEYWORDS: &[&str] = &["CONSTRAINT", "PRIMARY", "UNIQUE", "FOREIGN", "CHECK"];
+
+/// 解析并校验后的 CSV 数据，列顺序与建表 SQL 一致
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CsvTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    /// 重新输出为带表头的 CSV 文本
+    pub fn to_csv(&self) -> Result<String, AppError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record(&self.columns)
+            .map_err(AppError::internal)?;
+        for row in &self.rows {
+            writer.write_record(row).map_err(AppError::internal)?;
+        }
+        let bytes = writer.into_inner().map_err(AppError::internal)?;
+        String::from_utf8(bytes).map_err(AppError::internal)
+    }
+}
+
+/// 将上传内容解码为 UTF-8 文本，按 gzip 魔数自动解压
+pub fn decode_upload(bytes: &[u8]) -> Result<String, AppError> {
+    let raw = if bytes.starts_with(&GZIP_MAGIC) {
+        let mut out = Vec::new();
+        GzDecoder::new(bytes)
+            .take(MAX_UPLOAD_BYTES as u64 + 1)
+            .read_to_end(&mut out)
+            .map_err(|e| AppError::validation(format!("gzip 解压失败: {e}")))?;
+        out
+    } else {
+        bytes.to_vec()
+    };
+    if raw.len() > MAX_UPLOAD_BYTES {
+        return Err(AppError::validation(format!(
+            "CSV 数据超过 {} MB 上限",
+            MAX_UPLOAD_BYTES / 1024 / 1024
+        )));
+    }
+
+    let text = String::from_utf8(raw).map_err(|_| AppError::validation("CSV 必须为 UTF-8 编码"))?;
+    Ok(text.strip_prefix('\u{feff}').unwrap_or(&text).to_string())
+}
+
+/// 从 `CREATE TABLE` 语句中提取列名，无法识别时返回空列表。
+///
+/// 建表 SQL 中可能还有 `CREATE INDEX` 等语句，只取第一条建表语句自身括号内的列定义。
+pub fn table_columns(create_sql: &str) -> Vec<String> {
+    let Some(body) = create_sql.split(';').find_map(create_table_body) else {
+        return Vec::new();
+    };
+
+    split_top_level(body)
+        .into_iter()
+        .filter_map(|def| {
+            let name = def.split_whitespace().next()?;
+            if CONSTRAINT_KEYWORDS
+                .iter()
+                .any(|kw| name.eq_ignore_ascii_case(kw))
+            {
+                return None;
+            }
+            Some(unquote_ident(name).to_string())
+        })
+        .collect()
+}
+
+/// 解析 CSV 文本：首行为表头。
+///
+/// `expected` 非空时表头必须与之完全匹配（忽略大小写与顺序），
+/// 数据按 `expected` 的列顺序重排；为空时直接采用表头作为列。
+pub fn parse_csv(text: &str, expected: &[String]) -> Result<CsvTable, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| AppError::validation(format!("CSV 表头解析失败: {e}")))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    if headers.iter().all(|h| h.is_empty()) {
+        return Err(AppError::validation("CSV 缺少表头"));
+    }
+
+    let (columns, order) = if expected.is_empty() {
+        (headers.clone(), (0..headers.len()).collect::<Vec<_>>())
+    } else {
+        (expected.to_vec(), column_order(&headers, expected)?)
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| {
+            let line = e.position().map_or(0, |pos| pos.line());
+            AppError::validation(format!("CSV 第 {line} 行解析失败: {e}"))
+        })?;
+        rows.push(order.iter().map(|&idx| record[idx].to_string()).collect());
+    }
+
+    Ok(CsvTable { columns, rows })
+}
+
+/// 计算 `expected` 中每一列在 CSV 表头中的位置
+fn column_order(headers: &[String], expected: &[String]) -> Result<Vec<usize>, AppError> {
+    let find = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+
+    let missing: Vec<&str> = expected
+        .iter()
+        .filter(|col| find(col).is_none())
+        .map(String::as_str)
+        .collect();
+    let unknown: Vec<&str> = headers
+        .iter()
+        .filter(|h| !expected.iter().any(|col| col.eq_ignore_ascii_case(h)))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() || !unknown.is_empty() {
+        return Err(AppError::validation(format!(
+            "CSV 列与建表 SQL 不一致，缺少: [{}]，多余: [{}]",
+            missing.join(", "),
+            unknown.join(", ")
+        )));
+    }
+
+    Ok(expected.iter().filter_map(|col| find(col)).collect())
+}
+
+/// 按顶层逗号切分列定义，忽略括号内的逗号（如 `DECIMAL(10, 2)`）
+/// 建表语句中列定义所在的括号内容（不含外层括号），按括号配对截取，跳过引号内的括号
+fn create_table_body(stmt: &str) -> Option<&str> {
+    let start = stmt.find('(')?;
+    let words: Vec<&str> = stmt[..start].split_whitespace().collect();
+    let create = words
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case("CREATE"))?;
+    if !words[create + 1..]
+        .iter()
+        .any(|word| word.eq_ignore_ascii_case("TABLE"))
+    {
+        return None;
+    }
+
+    let mut depth = 0i32;
+    let mut quote = None;
+    for (idx, c) in stmt[start..].char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(&stmt[start + 1..start + idx]);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+fn split_top_level(body: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0usize;
+    for (idx, c) in body.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(body[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(body[start..].trim());
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+fn unquote_ident(name: &str) -> &str {
+    name.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'))
+}
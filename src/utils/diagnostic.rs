@@ -0,0 +1,145 @@
+// 结构化诊断：为规则语法错误与日志解析错误附加严重级别、错误码与位置信息
+
+use regex::Regex;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+/// WPL 规则语法错误
+pub const WPL_SYNTAX: &str = "wpl-syntax";
+/// 日志数据不匹配 WPL 规则
+pub const WPL_DATA: &str = "wpl-data";
+/// OML 模型语法错误
+pub const OML_SYNTAX: &str = "oml-syntax";
+
+/// 引擎错误文本中的位置描述，按优先级排列：
+/// `line 3, column 5`、`第 3 行第 5 列`、只有行号的 `line 3`。
+///
+/// 引擎的解析器基于 winnow，语法错误文本沿用其 `ParseError` 的
+/// `parse error at line N, column M`（列按字符计数），此处按该格式读取位置。
+static POSITION_PATTERNS: LazyLock<[Regex; 3]> = LazyLock::new(|| {
+    [
+        Regex::new(r"(?i)\bline\s*[:=]?\s*(\d+)\s*,?\s*col(?:umn)?\s*[:=]?\s*(\d+)").unwrap(),
+        Regex::new(r"第\s*(\d+)\s*行\s*,?\s*第?\s*(\d+)\s*列").unwrap(),
+        Regex::new(r"(?i)\bline\s*[:=]?\s*(\d+)").unwrap(),
+    ]
+});
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// 源码位置，行列均从 1 开始，列按字符计数
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePos {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// 源码区间，`end` 不包含在区间内
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceRange {
+    pub start: SourcePos,
+    pub end: SourcePos,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// 诊断类别，如 [`WPL_SYNTAX`]
+    pub code: &'static str,
+    /// 规则源码中的位置，引擎未给出位置时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<SourceRange>,
+    /// 日志数据中解析失败处的字节偏移
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_offset: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            code,
+            range: None,
+            log_offset: None,
+        }
+    }
+
+    /// 规则语法错误：从引擎错误文本中定位源码位置
+    pub fn syntax(code: &'static str, source: &str, error: impl Display) -> Self {
+        let message = error.to_string();
+        let range = locate(source, &message);
+        Self {
+            range,
+            ..Self::error(code, message)
+        }
+    }
+
+    pub fn with_log_offset(mut self, offset: usize) -> Self {
+        self.log_offset = Some(offset);
+        self
+    }
+}
+
+/// 从错误文本中解析位置并对齐到源码。
+///
+/// 起点落在标识符上时区间覆盖整个标识符，否则覆盖单个字符；
+/// 行号超出源码范围时返回 None。
+pub fn locate(source: &str, message: &str) -> Option<SourceRange> {
+    let (line, column) = POSITION_PATTERNS.iter().find_map(|pattern| {
+        let caps = pattern.captures(message)?;
+        let line = caps.get(1)?.as_str().parse::<usize>().ok()?;
+        let column = caps
+            .get(2)
+            .and_then(|col| col.as_str().parse::<usize>().ok());
+        Some((line, column))
+    })?;
+    let content = source.lines().nth(line.checked_sub(1)?)?;
+    let chars: Vec<char> = content.chars().collect();
+
+    // 只有行号时指向首个非空白字符
+    let start = match column {
+        Some(column) => column.clamp(1, chars.len() + 1) - 1,
+        None => chars.iter().position(|c| !c.is_whitespace()).unwrap_or(0),
+    };
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let end = match chars.get(start) {
+        Some(c) if is_word(c) => start + chars[start..].iter().take_while(|c| is_word(c)).count(),
+        Some(_) => start + 1,
+        None => start,
+    };
+
+    Some(SourceRange {
+        start: SourcePos {
+            line,
+            column: start + 1,
+        },
+        end: SourcePos {
+            line,
+            column: end + 1,
+        },
+    })
+}
+
+/// 将源码位置换算为字节偏移，位置不在源码内时返回 None
+pub fn offset_of(source: &str, pos: SourcePos) -> Option<usize> {
+    let mut line_start = 0usize;
+    for _ in 1..pos.line {
+        line_start += source[line_start..].find('\n')? + 1;
+    }
+    let line = source[line_start..].split('\n').next()?;
+    let column = pos.column.checked_sub(1)?;
+    let in_line = line
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(line.len()))
+        .nth(column)?;
+    Some(line_start + in_line)
+}
@@ -0,0 +1,18 @@
+// 文本差异：生成统一格式（unified）的 diff
+
+use similar::TextDiff;
+
+/// 差异上下文行数
+const CONTEXT_LINES: usize = 3;
+
+/// 生成 `old` 到 `new` 的统一格式 diff，内容相同时返回空字符串
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .header(old_label, new_label)
+        .to_string()
+}
@@ -0,0 +1,252 @@
+// WPL/OML 格式化风格：缩进、行宽、赋值对齐与尾随逗号，可由规则仓库的 `.wpfmt.toml` 配置
+
+use crate::error::AppError;
+use crate::utils::diff::unified_diff;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// 规则仓库根目录下的格式化配置文件
+pub const CONFIG_FILE: &str = ".wpfmt.toml";
+
+/// 字段列表的尾随逗号策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingComma {
+    /// 保持原文
+    #[default]
+    Preserve,
+    /// 多行字段列表的最后一项补齐逗号
+    Always,
+    /// 移除最后一项后的逗号
+    Never,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FormatOptions {
+    /// 每级缩进的空格数，使用制表符时仅用于计算行宽
+    pub indent_width: usize,
+    /// 使用制表符缩进
+    pub use_tabs: bool,
+    /// 行宽上限，超出时将管道链拆到多行；0 表示不限制
+    pub max_width: usize,
+    /// 对齐 OML 相邻赋值语句的 `=`
+    pub align_assignments: bool,
+    /// WPL 多行字段分组的尾随逗号
+    pub trailing_comma: TrailingComma,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+            max_width: 0,
+            align_assignments: false,
+            trailing_comma: TrailingComma::Preserve,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// 读取仓库根目录下的 [`CONFIG_FILE`]，文件不存在时使用默认风格
+    pub fn load(repo: &Path) -> Result<Self, AppError> {
+        let path = repo.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(AppError::internal)?;
+        toml::from_str(&content).map_err(|e| {
+            AppError::validation(format!("格式化配置 {} 解析失败: {e}", path.display()))
+        })
+    }
+
+    /// 单级缩进
+    pub fn indent_unit(&self) -> String {
+        if self.use_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.indent_width.max(1))
+        }
+    }
+}
+
+/// 请求中逐项覆盖仓库配置的格式化选项
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FormatOverrides {
+    pub indent_width: Option<usize>,
+    pub use_tabs: Option<bool>,
+    pub max_width: Option<usize>,
+    pub align_assignments: Option<bool>,
+    pub trailing_comma: Option<TrailingComma>,
+}
+
+impl FormatOverrides {
+    pub fn apply(&self, base: FormatOptions) -> FormatOptions {
+        FormatOptions {
+            indent_width: self.indent_width.unwrap_or(base.indent_width),
+            use_tabs: self.use_tabs.unwrap_or(base.use_tabs),
+            max_width: self.max_width.unwrap_or(base.max_width),
+            align_assignments: self.align_assignments.unwrap_or(base.align_assignments),
+            trailing_comma: self.trailing_comma.unwrap_or(base.trailing_comma),
+        }
+    }
+}
+
+/// 格式检查结果：只报告差异，不改写原文
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FormatCheck {
+    /// 内容是否已是格式化后的结果
+    pub formatted: bool,
+    /// 原文到格式化结果的统一 diff，已格式化时为空
+    pub diff: String,
+}
+
+impl FormatCheck {
+    pub fn new(original: &str, formatted: &str, label: &str) -> Self {
+        Self {
+            formatted: original == formatted,
+            diff: unified_diff(
+                original,
+                formatted,
+                &format!("a/{label}"),
+                &format!("b/{label}"),
+            ),
+        }
+    }
+}
+
+/// 按显示宽度计算行长，制表符按缩进宽度计
+fn line_width(line: &str, options: &FormatOptions) -> usize {
+    line.chars()
+        .map(|c| if c == '\t' { options.indent_width } else { 1 })
+        .sum()
+}
+
+/// 行内顶层管道符 ` | ` 的字节位置，跳过括号内、转义与行注释之后的内容。
+///
+/// `quotes` 为 true 时同时跳过字符串内容；WPL 中 `"` 可作为字段后缀，不能按字符串配对。
+fn top_level_pipes(line: &str, quotes: bool) -> Vec<usize> {
+    let mut pipes = Vec::new();
+    let mut depth = 0i32;
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut prev = '\0';
+    for (idx, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            prev = c;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' if quotes => in_str = !in_str,
+            _ if in_str => {}
+            '/' if prev == '/' => break,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '|' if depth == 0 && line[idx + 1..].starts_with(' ') => pipes.push(idx),
+            _ => {}
+        }
+        prev = c;
+    }
+    pipes
+}
+
+/// 标记每一行是否处于跨行字符串字面量中：行首位于字符串内，或行尾仍有未闭合的字符串。
+///
+/// 这些行的内容属于字面量，按行处理的排版步骤（管道换行、赋值对齐）需原样保留。
+/// 行注释 `//` 之后的引号不参与配对。
+pub(crate) fn literal_lines(text: &str) -> Vec<bool> {
+    let mut marks = Vec::new();
+    let mut in_str = false;
+    for line in text.lines() {
+        let starts_in_str = in_str;
+        let mut escaped = false;
+        let mut prev = '\0';
+        for c in line.chars() {
+            if escaped {
+                escaped = false;
+                prev = c;
+                continue;
+            }
+            match c {
+                '\\' => escaped = true,
+                '"' => in_str = !in_str,
+                '/' if !in_str && prev == '/' => break,
+                _ => {}
+            }
+            prev = c;
+        }
+        marks.push(starts_in_str || in_str);
+    }
+    marks
+}
+
+/// 启用行宽限制时整理管道链：先把被拆到行首的 `| ...` 合并回上一行，
+/// 再将超出行宽的行在顶层管道处拆开，续行比原行多缩进一级。
+pub(crate) fn wrap_pipes(text: &str, options: &FormatOptions, quotes: bool) -> String {
+    if options.max_width == 0 {
+        return text.to_string();
+    }
+
+    // 跨行字符串字面量所在的行原样保留，既不参与合并也不拆分
+    let literal = if quotes {
+        literal_lines(text)
+    } else {
+        Vec::new()
+    };
+
+    // 合并：上一行以块/分组起始、逗号或注释结尾时保持独立
+    let mut joined: Vec<(String, bool)> = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let in_literal = literal.get(idx).copied().unwrap_or(false);
+        let content = line.trim_start();
+        let join = !in_literal
+            && content.starts_with('|')
+            && joined.last().is_some_and(|(prev, prev_literal)| {
+                let prev = prev.trim();
+                !prev_literal
+                    && !prev.is_empty()
+                    && !prev.ends_with(['{', '(', ',', ';'])
+                    && !prev.starts_with("#[")
+                    && !prev.contains("//")
+                    && !prev.ends_with("*/")
+            });
+        match joined.last_mut() {
+            Some((prev, _)) if join => {
+                prev.push(' ');
+                prev.push_str(content);
+            }
+            _ => joined.push((line.to_string(), in_literal)),
+        }
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for (line, in_literal) in joined {
+        let content = line.trim_start();
+        let indent = &line[..line.len() - content.len()];
+        let pipes: Vec<usize> = top_level_pipes(content, quotes)
+            .into_iter()
+            .filter(|&pos| pos > 0)
+            .collect();
+        if in_literal || pipes.is_empty() || line_width(&line, options) <= options.max_width {
+            out.push_str(&line);
+            out.push('\n');
+            continue;
+        }
+        let continuation = format!("{indent}{}", options.indent_unit());
+        let mut start = 0usize;
+        for pos in pipes.into_iter().chain(std::iter::once(content.len())) {
+            let segment = content[start..pos].trim();
+            let prefix = if start == 0 { indent } else { &continuation };
+            out.push_str(prefix);
+            out.push_str(segment);
+            out.push('\n');
+            start = pos;
+        }
+    }
+    out
+}
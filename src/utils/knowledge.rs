@@ -1,14 +1,61 @@
+use crate::db::KnowledgeConfig;
 use crate::error::AppError;
-use std::{collections::HashMap, path::PathBuf};
+use crate::utils::oml;
+use crate::utils::sql_guard::{StatementKind, check_read_only};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 use tracing::info;
 use wp_data_utils::cache::FieldQueryCache;
 use wp_knowledge::facade;
 use wp_model_core::model::{DataField, DataRecord};
 use wp_oml::{DataRecordRef, core::FieldExtractor, language::SqlQuery, types::AnyResult};
 
-pub fn db_init(_connection_id: i32) -> AnyResult<Vec<DataField>> {
-    //todo 写一个加载 project_root/1/models/knowledge下所有数据的方法
-    Ok(vec![])
+/// 知识库在工程目录下的相对位置，与 `load_knowledge` 读取的路径一致
+pub const KNOWDB_DIR: &str = "models/knowledge";
+
+/// 将激活的知识库配置物化为 knowdb 目录结构，返回物化的配置数量。
+///
+/// 每个配置生成 `<file_name>/create.sql`、`insert.sql`、`data.csv`，
+/// 各配置的 TOML 片段依次拼接为 `knowdb.toml`。目录会先清空再重建。
+pub fn materialize_configs(root: &Path, configs: &[KnowledgeConfig]) -> Result<usize, AppError> {
+    let knowdb_dir = root.join(KNOWDB_DIR);
+    if knowdb_dir.exists() {
+        fs::remove_dir_all(&knowdb_dir).map_err(AppError::internal)?;
+    }
+    fs::create_dir_all(&knowdb_dir).map_err(AppError::internal)?;
+    fs::create_dir_all(root.join(".run")).map_err(AppError::internal)?;
+
+    let mut knowdb = String::new();
+    let mut count = 0usize;
+    for config in configs.iter().filter(|config| config.is_active) {
+        let name = config.file_name.as_str();
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            warn!("跳过非法的知识库配置名称: {}", name);
+            continue;
+        }
+
+        let dir = knowdb_dir.join(name);
+        fs::create_dir_all(&dir).map_err(AppError::internal)?;
+        for (file, content) in [
+            ("create.sql", &config.create_sql),
+            ("insert.sql", &config.insert_sql),
+            ("data.csv", &config.data_content),
+        ] {
+            if let Some(content) = content {
+                fs::write(dir.join(file), content).map_err(AppError::internal)?;
+            }
+        }
+        if let Some(content) = config.config_content.as_deref() {
+            knowdb.push_str(content.trim_end());
+            knowdb.push_str("\n\n");
+        }
+        count += 1;
+    }
+
+    fs::write(knowdb_dir.join("knowdb.toml"), knowdb).map_err(AppError::internal)?;
+    Ok(count)
 }
 
 pub fn sql_query(_connection_id: i32, sql: &str) -> AnyResult<Vec<DataField>> {
@@ -24,27 +71,15 @@ pub fn sql_query(_connection_id: i32, sql: &str) -> AnyResult<Vec<DataField>> {
 }
 
 pub fn sql_knowdb_list(_connection_id: i32) -> AnyResult<Vec<String>> {
-    let sql = r#"SELECT GROUP_CONCAT(name, ', ') as name FROM sqlite_master WHERE type='table'"#;
-    let cache = &mut FieldQueryCache::default();
-    let query = SqlQuery::new(sql.to_string(), HashMap::default());
-    let result = query.extract_more(
-        &mut DataRecordRef::from(&DataRecord::default()),
-        &DataRecord::default(),
-        cache,
-    );
-    info!("查询知识库列表: {:?}", result);
-    match result.first() {
-        Some(value) => {
-            let list = format!("{}", value.get_value());
-            info!("{}", list);
-            let items: Vec<String> = list
-                .split(',')
-                .map(|s: &str| s.trim().to_string())
-                .collect();
-            Ok(items)
-        }
-        None => Ok(vec![]),
-    }
+    let sql = "SELECT name FROM sqlite_master \
+               WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name";
+    let (_, rows) = query_rows(sql)?;
+    let tables = rows
+        .into_iter()
+        .filter_map(|row| row.into_iter().next())
+        .collect();
+    info!("查询知识库列表: {:?}", tables);
+    Ok(tables)
 }
 
 pub fn load_knowledge(project_dir: &str) -> AnyResult<()> {
@@ -70,3 +105,295 @@ pub fn load_knowledge(project_dir: &str) -> AnyResult<()> {
     }
     Ok(())
 }
+
+/// 知识库调试查询默认每页行数
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+/// 知识库调试查询单页最大行数
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// 分页查询结果，单元格统一渲染为字符串
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct QueryPage {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// 查询结果总行数（分页前）
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+/// 对已加载的知识库表执行调试查询。
+///
+/// `sql` 为空时查询 `table` 整张表，此时 `table` 必填；否则 `sql` 必须是单条 SELECT 语句，
+/// `table` 可省略，给出时 SQL 必须引用该表。
+/// `page` 从 1 开始，`page_size` 为 0 时取默认值，超过 [`MAX_PAGE_SIZE`] 时截断。
+pub fn query_page(
+    table: Option<&str>,
+    sql: &str,
+    page: usize,
+    page_size: usize,
+) -> Result<QueryPage, AppError> {
+    let table = table.map(str::trim).filter(|table| !table.is_empty());
+    if let Some(table) = table {
+        validate_table_name(table)?;
+        let tables = sql_knowdb_list(0).map_err(AppError::knowledge_query)?;
+        if !tables.iter().any(|name| name == table) {
+            return Err(AppError::not_found(format!("知识库表 {table}")));
+        }
+    }
+
+    let sql = if sql.trim().is_empty() {
+        let table = table.ok_or_else(|| AppError::validation("未提供 SQL 时必须指定知识库表"))?;
+        format!("SELECT * FROM {table}")
+    } else {
+        let query = check_read_only(sql)?;
+        if query.kind != StatementKind::Select {
+            return Err(AppError::validation("分页查询只支持 SELECT 语句"));
+        }
+        if let Some(table) = table
+            && !query.references(table)
+        {
+            return Err(AppError::validation(format!(
+                "SQL 未引用指定的知识库表 {table}"
+            )));
+        }
+        query.sql
+    };
+    let page = page.max(1);
+    let page_size = match page_size {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    };
+
+    let total = facade::query(&format!("SELECT COUNT(*) FROM ({sql})"))
+        .map_err(AppError::knowledge_query)?
+        .first()
+        .and_then(|row| row.first())
+        .and_then(|field| field.value.to_string().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let (columns, rows) = query_rows(&paged_sql(&sql, page, page_size)?)?;
+
+    Ok(QueryPage {
+        columns,
+        rows,
+        total,
+        page,
+        page_size,
+    })
+}
+
+/// 执行查询并将结果拆分为列名与字符串单元格。
+///
+/// 结果为空时通过 [`probe_columns`] 取列名，保证前端仍能渲染表头。
+/// 调用方负责校验 SQL，见 [`check_read_only`]。
+pub fn query_rows(sql: &str) -> Result<(Vec<String>, Vec<Vec<String>>), AppError> {
+    let rows = facade::query(sql).map_err(AppError::knowledge_query)?;
+    let columns = match rows.first() {
+        Some(row) => row.iter().map(|field| field.name.to_string()).collect(),
+        None => probe_columns(sql),
+    };
+    let rows = rows
+        .iter()
+        .map(|row| row.iter().map(|field| field.value.to_string()).collect())
+        .collect();
+    Ok((columns, rows))
+}
+
+/// 取查询语句的结果列名。
+///
+/// 知识库查询接口只返回数据行，列名随行给出。这里以单行常量表左连接原查询，
+/// 原查询无结果时也会得到一行全为 NULL 的记录，从中读取列名。
+/// 无法作为子查询的语句（如 PRAGMA）返回空列表。
+fn probe_columns(sql: &str) -> Vec<String> {
+    let probe = format!("SELECT t.* FROM (SELECT 1) LEFT JOIN ({sql}) AS t LIMIT 1");
+    facade::query(&probe)
+        .ok()
+        .and_then(|rows| rows.into_iter().next())
+        .map(|row| row.iter().map(|field| field.name.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// 知识库表结构默认采样行数
+pub const DEFAULT_SAMPLE_ROWS: usize = 5;
+/// 知识库表结构最大采样行数
+pub const MAX_SAMPLE_ROWS: usize = 50;
+
+/// 知识库表的列定义
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TableColumn {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub primary_key: bool,
+}
+
+/// 知识库表的索引
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TableIndex {
+    pub name: String,
+    pub unique: bool,
+    pub columns: Vec<String>,
+}
+
+/// 引用知识库表的 OML 模型
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct OmlTableRef {
+    /// OML 头部声明的模型名称，缺省时为文件名
+    pub model: String,
+    /// 相对 OML 仓库根目录的路径
+    pub path: String,
+}
+
+/// 知识库表结构概览
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<TableColumn>,
+    pub row_count: usize,
+    pub indexes: Vec<TableIndex>,
+    /// 采样数据，列顺序与 `columns` 一致
+    pub sample_rows: Vec<Vec<String>>,
+    pub referenced_by: Vec<OmlTableRef>,
+}
+
+/// 读取已加载知识库中所有表的结构、行数、索引与采样数据
+pub fn knowdb_schema(sample_rows: usize) -> Result<Vec<TableSchema>, AppError> {
+    let sample_rows = sample_rows.min(MAX_SAMPLE_ROWS);
+    sql_knowdb_list(0)
+        .map_err(AppError::knowledge_query)?
+        .iter()
+        .map(|table| table_schema(table, sample_rows))
+        .collect()
+}
+
+/// 读取单张知识库表的结构，`referenced_by` 由调用方填充
+pub fn table_schema(table: &str, sample_rows: usize) -> Result<TableSchema, AppError> {
+    validate_table_name(table)?;
+
+    // pragma_table_info 返回: cid, name, type, notnull, dflt_value, pk
+    let (_, rows) = query_rows(&format!(
+        "SELECT name, type, \"notnull\", pk FROM pragma_table_info('{table}')"
+    ))?;
+    let columns = rows
+        .into_iter()
+        .map(|row| {
+            let cell = |idx: usize| row.get(idx).cloned().unwrap_or_default();
+            TableColumn {
+                name: cell(0),
+                data_type: cell(1),
+                not_null: cell(2) != "0",
+                primary_key: cell(3) != "0",
+            }
+        })
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return Err(AppError::not_found(format!("知识库表 {table}")));
+    }
+
+    let (_, rows) = query_rows(&format!(
+        "SELECT name, \"unique\" FROM pragma_index_list('{table}') ORDER BY name"
+    ))?;
+    let mut indexes = Vec::with_capacity(rows.len());
+    for row in rows {
+        let [name, unique] = <[String; 2]>::try_from(row)
+            .map_err(|_| AppError::knowledge_query("索引信息格式不正确"))?;
+        let (_, index_columns) = query_rows(&format!(
+            "SELECT name FROM pragma_index_info('{}') ORDER BY seqno",
+            name.replace('\'', "''")
+        ))?;
+        indexes.push(TableIndex {
+            name,
+            unique: unique != "0",
+            columns: index_columns.into_iter().flatten().collect(),
+        });
+    }
+
+    let row_count = query_rows(&format!("SELECT COUNT(*) FROM {table}"))?
+        .1
+        .first()
+        .and_then(|row| row.first())
+        .and_then(|cell| cell.parse::<usize>().ok())
+        .unwrap_or(0);
+    let (_, sample_rows) = query_rows(&format!("SELECT * FROM {table} LIMIT {sample_rows}"))?;
+
+    Ok(TableSchema {
+        name: table.to_string(),
+        columns,
+        row_count,
+        indexes,
+        sample_rows,
+        referenced_by: Vec::new(),
+    })
+}
+
+/// 扫描 OML 仓库，按表名（小写）汇总引用该知识库表的模型
+pub fn oml_table_refs(oml_repo: &Path) -> BTreeMap<String, Vec<OmlTableRef>> {
+    let mut refs: BTreeMap<String, Vec<OmlTableRef>> = BTreeMap::new();
+    let mut pending = vec![oml_repo.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if path.extension().and_then(|ext| ext.to_str()) != Some("oml") {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path) else {
+                warn!("读取 OML 文件失败: {}", path.display());
+                continue;
+            };
+
+            let rel_path = path
+                .strip_prefix(oml_repo)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let model = oml::model_name(&content).unwrap_or_else(|| rel_path.clone());
+            for table in oml::referenced_tables(&content) {
+                refs.entry(table.to_ascii_lowercase())
+                    .or_default()
+                    .push(OmlTableRef {
+                        model: model.clone(),
+                        path: rel_path.clone(),
+                    });
+            }
+        }
+    }
+    for models in refs.values_mut() {
+        models.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    refs
+}
+
+/// 表名只允许字母、数字与下划线，避免拼接 SQL 时注入
+pub fn validate_table_name(table: &str) -> Result<(), AppError> {
+    let valid = table
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::validation(format!("非法的知识库表名: {table}")))
+    }
+}
+
+/// 以子查询包装原始 SQL，附加分页条件。
+///
+/// `page` 从 1 开始；偏移量溢出或超出 SQLite 整数范围时返回校验错误。
+pub fn paged_sql(sql: &str, page: usize, page_size: usize) -> Result<String, AppError> {
+    let offset = page
+        .checked_sub(1)
+        .and_then(|page| page.checked_mul(page_size))
+        .filter(|offset| i64::try_from(*offset).is_ok())
+        .ok_or_else(|| AppError::validation(format!("页码超出范围: {page}")))?;
+    Ok(format!(
+        "SELECT * FROM ({sql}) LIMIT {page_size} OFFSET {offset}"
+    ))
+}
@@ -1,11 +1,24 @@
 // 工具模块
 
+pub mod csv_import;
+pub mod diagnostic;
+pub mod diff;
+pub mod format_options;
+pub mod knowledge;
 pub mod oml;
 pub mod oml_formatter;
+pub mod perf;
+pub mod sql_guard;
 pub mod wpl;
 pub mod wpl_formatter;
 
+pub use format_options::{FormatOptions, TrailingComma};
 pub use oml::convert_record;
 pub use oml_formatter::OmlFormatter;
-pub use wpl::{ParsedField, record_to_fields, warp_check_record};
+pub use wpl::{
+    AnnotationDiff, BatchParseResult, BatchRecordResult, EventIdMode, FieldSpan, ParseOptions,
+    ParseOutcome, ParsedField, RuleMatchItem, RuleMatrix, TraceKind, TraceStatus, TraceStep,
+    WplTrace, record_spans, record_to_fields, warp_check_batch, warp_check_outcome,
+    warp_check_record, warp_rule_matrix, warp_trace,
+};
 pub use wpl_formatter::WplFormatter;
@@ -1,23 +1,181 @@
 use crate::error::AppError;
+use crate::utils::oml_formatter::RAW_FUNCS;
+use regex::Regex;
+use std::sync::LazyLock;
 use wp_data_utils::cache::FieldQueryCache;
 use wp_model_core::model::DataRecord;
 use wp_oml::{core::DataTransformer, parser::oml_parse};
 
 pub fn convert_record(oml: &str, record: DataRecord) -> Result<DataRecord, AppError> {
     // 预处理：去除注释
-    let filter_oml = oml
-        .lines()
-        .map(|line| {
-            if let Some(comment_start) = line.find("//") {
-                &line[0..comment_start]
-            } else {
-                line
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-    let model = oml_parse(&mut filter_oml.as_str(), "")?;
+    let filter_oml = strip_comments(oml);
+    let model =
+        oml_parse(&mut filter_oml.as_str(), "").map_err(|e| AppError::oml_syntax(oml, e))?;
     let mut cache = FieldQueryCache::with_capacity(10);
     let target = model.transform_ref(&record, &mut cache);
     Ok(target)
 }
+
+/// OML 模型头部的 `name : xxx`
+static MODEL_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*name\s*:\s*(\S+)").unwrap());
+
+/// 知识库查询 `select ... from <table>` 中的表名
+static SELECT_FROM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)\bselect\b[^;]*?\bfrom\s+([A-Za-z_][A-Za-z0-9_]*)").unwrap()
+});
+
+/// 读取 OML 模型头部声明的名称
+pub fn model_name(oml: &str) -> Option<String> {
+    MODEL_NAME.captures(oml).map(|caps| caps[1].to_string())
+}
+
+/// 提取 OML 中 `select ... from <table>` 引用的知识库表名（去重并排序，注释中的不计入）
+pub fn referenced_tables(oml: &str) -> Vec<String> {
+    let oml = strip_comments(oml);
+    let mut tables: Vec<String> = SELECT_FROM
+        .captures_iter(&oml)
+        .map(|caps| caps[1].to_string())
+        .collect();
+    tables.sort();
+    tables.dedup();
+    tables
+}
+
+/// 去除 OML 中的 `//` 行注释与 `/* */` 块注释，供解析前预处理。
+///
+/// 按词法扫描：字符串字面量（`"..."`、`'...'`、`r#"..."#`）与原样函数（如 `chars(...)`）
+/// 内部的 `//`、`/*` 不视为注释。块注释只在记号边界开始，且头部 `rule :` 的路径
+/// （如 `/nginx/*`）中的 `/*` 属于通配符、不视为注释。块注释中的换行会被保留，保证行号不变。
+pub fn strip_comments(oml: &str) -> String {
+    let mut out = String::with_capacity(oml.len());
+    let mut pos = 0usize;
+    // 位于 `---` 之前的头部，以及头部中 `rule :` 的路径部分
+    let mut in_header = true;
+    let mut in_rule_paths = false;
+
+    while pos < oml.len() {
+        let rest = &oml[pos..];
+        if in_header && (pos == 0 || oml[..pos].ends_with('\n')) {
+            let line = rest.split('\n').next().unwrap_or_default().trim_start();
+            if line.starts_with("---") {
+                in_header = false;
+                in_rule_paths = false;
+            } else if let Some(key) = header_key(line) {
+                in_rule_paths = key == "rule";
+            }
+        }
+        if rest.starts_with("//") {
+            // 保留行尾换行，由下一轮原样输出
+            pos += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        if !in_rule_paths
+            && at_token_boundary(oml, pos)
+            && let Some(body) = rest.strip_prefix("/*")
+        {
+            let len = body.find("*/").map_or(rest.len(), |end| end + 4);
+            out.push(' ');
+            out.extend(rest[..len].chars().filter(|&c| c == '\n'));
+            pos += len;
+            continue;
+        }
+
+        let len = if rest.starts_with(['"', '\'']) {
+            quoted_len(rest)
+        } else if at_word_start(oml, pos)
+            && let Some(len) = raw_string_len(rest).or_else(|| raw_func_len(rest, RAW_FUNCS))
+        {
+            len
+        } else {
+            rest.chars().next().map_or(1, char::len_utf8)
+        };
+        out.push_str(&rest[..len]);
+        pos += len;
+    }
+    out
+}
+
+/// 头部 `key : value` 行的键名
+fn header_key(line: &str) -> Option<&str> {
+    let (key, _) = line.split_once(':')?;
+    let key = key.trim_end();
+    (!key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(key)
+}
+
+/// `pos` 位于记号边界：行首、空白或分隔符之后
+fn at_token_boundary(src: &str, pos: usize) -> bool {
+    src[..pos]
+        .chars()
+        .next_back()
+        .is_none_or(|c| c.is_whitespace() || matches!(c, ';' | ',' | '(' | ')' | '='))
+}
+
+/// `pos` 前一个字符不是标识符字符，避免把 `xchars(` 之类识别为原样函数
+pub(crate) fn at_word_start(src: &str, pos: usize) -> bool {
+    src[..pos]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !(c.is_alphanumeric() || c == '_'))
+}
+
+/// 以首字符为引号（`"` 或 `'`）的字符串长度（含引号），支持 `\` 转义；未闭合时取到末尾
+pub(crate) fn quoted_len(rest: &str) -> usize {
+    let Some(quote) = rest.chars().next() else {
+        return 0;
+    };
+    let mut escaped = false;
+    for (idx, c) in rest.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return idx + 1,
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// 原始字符串 `r"..."` / `r#"..."#` 的长度；不是原始字符串时返回 None
+pub(crate) fn raw_string_len(rest: &str) -> Option<usize> {
+    let after_r = rest.strip_prefix('r')?;
+    let hashes = after_r.len() - after_r.trim_start_matches('#').len();
+    let body = after_r[hashes..].strip_prefix('"')?;
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let start = rest.len() - body.len();
+    Some(
+        body.find(&closing)
+            .map_or(rest.len(), |end| start + end + closing.len()),
+    )
+}
+
+/// 原样函数块 `name(...)` 的长度，匹配到首层闭合括号为止；未闭合时返回 None
+pub(crate) fn raw_func_len(rest: &str, names: &[&str]) -> Option<usize> {
+    let name = names.iter().find(|name| {
+        rest.strip_prefix(**name)
+            .is_some_and(|r| r.starts_with('('))
+    })?;
+
+    let mut depth = 0i32;
+    let mut in_str = false;
+    let mut escaped = false;
+    for (idx, c) in rest.char_indices().skip(name.len()) {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' => in_str = !in_str,
+            '(' if !in_str => depth += 1,
+            ')' if !in_str => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -1,6 +1,14 @@
+use crate::error::AppError;
+use crate::utils::format_options::{FormatOptions, literal_lines, wrap_pipes};
+use crate::utils::oml::strip_comments;
+use wp_oml::parser::oml_parse;
+
+/// 内容需原样保留的函数（如 `chars(...)`），格式化与去注释时都不解析其内部
+pub(crate) const RAW_FUNCS: &[&str] = &["chars"];
+
 /// OML 代码格式化器：保持语义不变，统一缩进/空行/行内空格与属性折叠。
 pub struct OmlFormatter {
-    indent: usize,
+    options: FormatOptions,
 }
 
 impl Default for OmlFormatter {
@@ -12,16 +20,39 @@ impl Default for OmlFormatter {
 impl OmlFormatter {
     /// 默认 4 空格缩进。
     pub fn new() -> Self {
-        Self { indent: 4 }
+        Self::with_options(FormatOptions::default())
     }
 
+    pub fn with_options(options: FormatOptions) -> Self {
+        Self { options }
+    }
+
+    /// 格式化 OML 源码。
+    ///
+    /// 源码无法被引擎解析时返回带源码位置的语法诊断；排版结果无法解析时报错，
+    /// 避免格式化改写模型。
+    pub fn format(&self, content: &str) -> Result<String, AppError> {
+        oml_parse(&mut strip_comments(content).as_str(), "")
+            .map_err(|e| AppError::oml_syntax(content, e))?;
+        let formatted = self
+            .layout(content)
+            .map_err(|()| AppError::internal("OML 格式化失败"))?;
+        if let Err(e) = oml_parse(&mut strip_comments(&formatted).as_str(), "") {
+            return Err(AppError::internal(format!(
+                "OML 格式化结果无法通过解析: {e}"
+            )));
+        }
+        Ok(formatted)
+    }
+
+    /// 仅做排版、不校验语法，适用于展示等容错场景；出错时返回原文。
     pub fn format_content(&self, content: &str) -> String {
-        self.format(content).unwrap_or_else(|_| content.to_string())
+        self.layout(content).unwrap_or_else(|_| content.to_string())
     }
 
-    fn format(&self, content: &str) -> Result<String, ()> {
+    fn layout(&self, content: &str) -> Result<String, ()> {
         let normalized = content.replace("\r\n", "\n").replace('\r', "\n");
-        let normalized = normalized.replace('\t', &" ".repeat(self.indent));
+        let normalized = normalized.replace('\t', &" ".repeat(self.options.indent_width));
 
         // 分离头部与主体
         let mut header = Vec::new();
@@ -52,7 +83,7 @@ impl OmlFormatter {
         }
 
         let mut out = String::new();
-        let indent_unit = " ".repeat(self.indent);
+        let indent_unit = self.options.indent_unit();
         let mut idx = 0usize;
         while idx < header.len() {
             let line = &header[idx];
@@ -91,6 +122,9 @@ impl OmlFormatter {
             idx += 1;
         }
         let mut body_formatted = self.format_body(&body_lines.join("\n"));
+        if self.options.align_assignments {
+            body_formatted = align_assignments(&body_formatted);
+        }
 
         if had_sep || !header.is_empty() {
             out.push_str("---\n");
@@ -107,18 +141,17 @@ impl OmlFormatter {
         if !out.ends_with('\n') {
             out.push('\n');
         }
-        Ok(out)
+        Ok(wrap_pipes(&out, &self.options, true))
     }
 
     fn format_body(&self, body: &str) -> String {
         let mut out = String::new();
         let mut chars = body.chars().peekable();
         let mut indent = 0usize;
-        let indent_unit = " ".repeat(self.indent);
+        let indent_unit = self.options.indent_unit();
         let mut start_of_line = true;
         let mut pending_newlines = 0usize;
         let mut after_eq = false;
-        const RAW_FUNCS: &[&str] = &["chars"];
 
         while let Some(ch) = chars.next() {
             if ch.is_whitespace() {
@@ -548,12 +581,104 @@ fn collapse_ws(s: &str) -> String {
     out.trim().to_string()
 }
 
-/// 折叠连续空行为单个空行
+/// 对齐相邻单行赋值语句的 `=`；缩进不同、空行、注释或多行语句会打断对齐分组，
+/// 跨行字符串字面量所在的行原样保留
+fn align_assignments(body: &str) -> String {
+    let lines: Vec<&str> = body.lines().collect();
+    let eq_pos: Vec<Option<usize>> = lines
+        .iter()
+        .zip(literal_lines(body))
+        .map(|(line, in_literal)| {
+            if in_literal {
+                None
+            } else {
+                assignment_eq(line)
+            }
+        })
+        .collect();
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+
+    let mut out = String::with_capacity(body.len());
+    let mut idx = 0usize;
+    while idx < lines.len() {
+        let Some(pos) = eq_pos[idx] else {
+            out.push_str(lines[idx]);
+            out.push('\n');
+            idx += 1;
+            continue;
+        };
+        let indent = indent_of(lines[idx]);
+        let mut group = vec![(lines[idx], pos)];
+        while let Some(&line) = lines.get(idx + group.len())
+            && let Some(pos) = eq_pos[idx + group.len()]
+            && indent_of(line) == indent
+        {
+            group.push((line, pos));
+        }
+        let width = group
+            .iter()
+            .map(|(line, pos)| line[..*pos].trim_end().chars().count())
+            .max()
+            .unwrap_or(0);
+        for (line, pos) in &group {
+            let (target, expr) = line.split_at(*pos);
+            out.push_str(&format!(
+                "{:<width$} = {}\n",
+                target.trim_end(),
+                expr[1..].trim_start()
+            ));
+        }
+        idx += group.len();
+    }
+    out
+}
+
+/// 单行赋值语句中顶层 `=` 的位置，跳过字符串、括号以及 `==`、`=>`、`!=` 等运算符
+fn assignment_eq(line: &str) -> Option<usize> {
+    let trimmed = line.trim();
+    if !trimmed.ends_with(';') || trimmed.starts_with("//") || trimmed.starts_with("#[") {
+        return None;
+    }
+    let bytes = line.as_bytes();
+    let mut depth = 0i32;
+    let mut in_str = false;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' => in_str = !in_str,
+            _ if in_str => {}
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '=' if depth == 0 => {
+                let prev = idx.checked_sub(1).map(|i| bytes[i]);
+                let next = bytes.get(idx + 1).copied();
+                let operator = matches!(prev, Some(b'=' | b'!' | b'<' | b'>'))
+                    || matches!(next, Some(b'=' | b'>'));
+                return (!operator).then_some(idx);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 折叠连续空行为单个空行，跨行字符串字面量所在的行原样保留
 fn collapse_blank_lines(text: &str) -> String {
     let mut result = String::new();
     let mut last_blank = false;
 
-    for line in text.lines() {
+    for (line, in_literal) in text.lines().zip(literal_lines(text)) {
+        if in_literal {
+            last_blank = false;
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
         let blank = line.trim().is_empty();
         if blank && last_blank {
             continue;
@@ -0,0 +1,281 @@
+// 规则性能测试：复用评估器批量执行 WPL（可选 OML），统计吞吐、延迟与内存分配
+
+use crate::error::AppError;
+use crate::utils::oml::strip_comments;
+use crate::utils::wpl::{
+    ParseOptions, build_evaluators, parse_rule_items, split_events, try_parse_with_rules,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "perf-alloc")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "perf-alloc")]
+use std::cell::Cell;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use wp_data_utils::cache::FieldQueryCache;
+use wp_oml::{core::DataTransformer, parser::oml_parse};
+
+/// 单次性能测试允许执行的最大事件数
+pub const MAX_BENCH_EVENTS: usize = 100_000;
+
+/// 未启用分配统计时报告中的说明
+pub const ALLOC_UNAVAILABLE: &str =
+    "未注册计数分配器（需启用 perf-alloc 特性构建），分配统计不可用";
+
+#[cfg(feature = "perf-alloc")]
+thread_local! {
+    static ALLOC_COUNT: Cell<u64> = const { Cell::new(0) };
+    static ALLOC_BYTES: Cell<u64> = const { Cell::new(0) };
+}
+
+/// 按线程统计分配次数与字节数的全局分配器。
+///
+/// 仅在启用 `perf-alloc` 特性时编译，并由二进制通过 `#[global_allocator]` 注册；
+/// 未注册时性能报告中的分配统计标记为不可用。
+#[cfg(feature = "perf-alloc")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "perf-alloc")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_alloc(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_alloc(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_alloc(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(feature = "perf-alloc")]
+fn record_alloc(size: usize) {
+    // 线程退出阶段 TLS 可能已销毁，此时忽略统计
+    let _ = ALLOC_COUNT.try_with(|c| c.set(c.get() + 1));
+    let _ = ALLOC_BYTES.try_with(|c| c.set(c.get() + size as u64));
+}
+
+#[cfg(feature = "perf-alloc")]
+fn alloc_snapshot() -> (u64, u64) {
+    (ALLOC_COUNT.with(|c| c.get()), ALLOC_BYTES.with(|c| c.get()))
+}
+
+#[cfg(not(feature = "perf-alloc"))]
+fn alloc_snapshot() -> (u64, u64) {
+    (0, 0)
+}
+
+/// 判断当前进程是否注册了 [`CountingAllocator`]；未启用特性时恒为 false
+#[cfg(feature = "perf-alloc")]
+fn alloc_tracking_enabled() -> bool {
+    let (before, _) = alloc_snapshot();
+    black_box(Box::new(0u64));
+    let (after, _) = alloc_snapshot();
+    after > before
+}
+
+#[cfg(not(feature = "perf-alloc"))]
+fn alloc_tracking_enabled() -> bool {
+    false
+}
+
+fn default_events() -> usize {
+    1_000
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BenchConfig {
+    pub rules: String,
+    /// 可选 OML，配置后每条解析成功的记录都会执行一次转换
+    #[serde(default)]
+    pub oml: Option<String>,
+    /// 样本日志，按 `delimiter` 切分为多条事件
+    pub logs: String,
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// 计时执行的事件总数，样本不足时循环复用
+    #[serde(default = "default_events")]
+    pub events: usize,
+    /// 预热事件数，不计入统计
+    #[serde(default)]
+    pub warmup: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct LatencyStats {
+    pub min_us: f64,
+    pub mean_us: f64,
+    pub p50_us: f64,
+    pub p99_us: f64,
+    pub max_us: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AllocStats {
+    /// 是否启用了分配统计（未注册计数分配器时为 false）
+    pub tracked: bool,
+    /// 分配统计不可用的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<&'static str>,
+    pub allocations: u64,
+    pub bytes: u64,
+    pub allocations_per_event: f64,
+    pub bytes_per_event: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BenchReport {
+    pub samples: usize,
+    pub events: usize,
+    pub success: usize,
+    pub failed: usize,
+    pub with_oml: bool,
+    pub total_ms: f64,
+    /// 每秒处理事件数
+    pub throughput_eps: f64,
+    pub latency: LatencyStats,
+    pub alloc: AllocStats,
+    /// 首个失败事件的错误信息，便于定位规则问题
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+}
+
+/// 执行性能测试：WPL/OML 只编译一次，按样本循环执行 `events` 条事件。
+///
+/// 计算密集，调用方应放在阻塞线程池中执行。
+pub fn run_benchmark(config: &BenchConfig) -> Result<BenchReport, AppError> {
+    if config.events == 0 || config.events > MAX_BENCH_EVENTS {
+        return Err(AppError::validation(format!(
+            "性能测试事件数需在 1 到 {MAX_BENCH_EVENTS} 之间"
+        )));
+    }
+    if config.warmup > MAX_BENCH_EVENTS {
+        return Err(AppError::validation(format!(
+            "预热事件数不能超过 {MAX_BENCH_EVENTS}"
+        )));
+    }
+
+    let samples: Vec<&str> = split_events(&config.logs, config.delimiter.as_deref())
+        .into_iter()
+        .map(|(_, event)| event)
+        .collect();
+    if samples.is_empty() {
+        return Err(AppError::validation("性能测试样本日志不能为空"));
+    }
+
+    let rule_items = parse_rule_items(&config.rules)?;
+    let evaluators = build_evaluators(&rule_items)?;
+
+    let filter_oml = config
+        .oml
+        .as_deref()
+        .filter(|oml| !oml.trim().is_empty())
+        .map(strip_comments);
+    let model = match filter_oml.as_deref() {
+        Some(source) => {
+            Some(oml_parse(&mut &*source, "").map_err(|e| AppError::oml_syntax(source, e))?)
+        }
+        None => None,
+    };
+    let mut cache = FieldQueryCache::with_capacity(10);
+
+    let options = ParseOptions::default();
+    let mut run_event = |data: &str| -> Result<(), AppError> {
+        let record = try_parse_with_rules(&rule_items, &evaluators, data, &options, 0)?.record;
+        if let Some(model) = &model {
+            black_box(model.transform_ref(&record, &mut cache));
+        } else {
+            black_box(record);
+        }
+        Ok(())
+    };
+
+    for &data in samples.iter().cycle().take(config.warmup) {
+        let _ = run_event(data);
+    }
+
+    let track_alloc = alloc_tracking_enabled();
+    let mut latencies = Vec::with_capacity(config.events);
+    let mut success = 0usize;
+    let mut first_error = None;
+
+    let (alloc_count_start, alloc_bytes_start) = alloc_snapshot();
+    let started = Instant::now();
+    for &data in samples.iter().cycle().take(config.events) {
+        let event_started = Instant::now();
+        let result = run_event(data);
+        latencies.push(event_started.elapsed());
+        match result {
+            Ok(()) => success += 1,
+            Err(e) => {
+                if first_error.is_none() {
+                    first_error = Some(e.to_string());
+                }
+            }
+        }
+    }
+    let total = started.elapsed();
+    let (alloc_count_end, alloc_bytes_end) = alloc_snapshot();
+
+    let events = latencies.len();
+    let alloc = if track_alloc {
+        let allocations = alloc_count_end - alloc_count_start;
+        let bytes = alloc_bytes_end - alloc_bytes_start;
+        AllocStats {
+            tracked: true,
+            unavailable: None,
+            allocations,
+            bytes,
+            allocations_per_event: allocations as f64 / events as f64,
+            bytes_per_event: bytes as f64 / events as f64,
+        }
+    } else {
+        AllocStats {
+            unavailable: Some(ALLOC_UNAVAILABLE),
+            ..Default::default()
+        }
+    };
+
+    Ok(BenchReport {
+        samples: samples.len(),
+        events,
+        success,
+        failed: events - success,
+        with_oml: model.is_some(),
+        total_ms: total.as_secs_f64() * 1_000.0,
+        throughput_eps: events as f64 / total.as_secs_f64().max(f64::EPSILON),
+        latency: latency_stats(&mut latencies),
+        alloc,
+        first_error,
+    })
+}
+
+fn latency_stats(latencies: &mut [Duration]) -> LatencyStats {
+    if latencies.is_empty() {
+        return LatencyStats::default();
+    }
+    latencies.sort_unstable();
+    let to_us = |d: &Duration| d.as_secs_f64() * 1_000_000.0;
+    let percentile = |p: f64| {
+        let rank = ((latencies.len() as f64) * p).ceil() as usize;
+        to_us(&latencies[rank.clamp(1, latencies.len()) - 1])
+    };
+    let sum: Duration = latencies.iter().sum();
+
+    LatencyStats {
+        min_us: to_us(&latencies[0]),
+        mean_us: to_us(&sum) / latencies.len() as f64,
+        p50_us: percentile(0.50),
+        p99_us: percentile(0.99),
+        max_us: to_us(&latencies[latencies.len() - 1]),
+    }
+}
@@ -0,0 +1,307 @@
+// 知识库 SQL 沙箱：只允许单条只读语句，并限制执行时间与返回行数
+
+use crate::error::AppError;
+use crate::server::Setting;
+use serde::Serialize;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// 允许作为语句开头的关键字
+const READ_ONLY_STARTS: &[&str] = &["SELECT", "WITH", "EXPLAIN"];
+
+/// 会修改数据或访问其他数据库的关键字，出现在语句任意位置（字符串、标识符除外）都拒绝
+const FORBIDDEN_KEYWORDS: &[&str] = &[
+    "INSERT",
+    "UPDATE",
+    "DELETE",
+    "REPLACE",
+    "UPSERT",
+    "DROP",
+    "CREATE",
+    "ALTER",
+    "ATTACH",
+    "DETACH",
+    "PRAGMA",
+    "VACUUM",
+    "REINDEX",
+    "ANALYZE",
+    "BEGIN",
+    "COMMIT",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "RELEASE",
+];
+
+/// 语句类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementKind {
+    /// `SELECT` 或 `WITH ... SELECT`，可包装子查询限制行数
+    Select,
+    /// `EXPLAIN ...`，只能在取回结果后截断
+    Explain,
+}
+
+/// 通过校验的只读语句：原文去除注释与末尾分号，其余字符保持不变
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOnlyQuery {
+    pub sql: String,
+    pub kind: StatementKind,
+}
+
+impl ReadOnlyQuery {
+    /// 生成多取一行的 SQL，用于判断结果是否被截断
+    pub fn limited_sql(&self, max_rows: usize) -> String {
+        match self.kind {
+            StatementKind::Select => {
+                format!("SELECT * FROM ({}) LIMIT {}", self.sql, max_rows + 1)
+            }
+            StatementKind::Explain => self.sql.clone(),
+        }
+    }
+
+    /// 语句中是否以标识符形式引用了 `name`（忽略大小写，可带引号），字符串字面量不计入
+    pub fn references(&self, name: &str) -> bool {
+        tokenize(&self.sql).is_ok_and(|lexed| {
+            lexed.tokens.iter().any(|(token, _)| match token {
+                Token::Word(word) => word.eq_ignore_ascii_case(name),
+                Token::Quoted(text) if !text.starts_with('\'') => {
+                    text[1..text.len() - 1].eq_ignore_ascii_case(name)
+                }
+                _ => false,
+            })
+        })
+    }
+}
+
+/// 查询限制，默认值取自 `[knowledge]` 配置
+#[derive(Debug, Clone, Copy)]
+pub struct QueryLimits {
+    pub timeout: Duration,
+    pub max_rows: usize,
+}
+
+impl QueryLimits {
+    pub fn from_setting() -> Self {
+        let conf = Setting::load().knowledge;
+        Self {
+            timeout: Duration::from_millis(conf.query_timeout_ms),
+            max_rows: conf.max_rows,
+        }
+    }
+}
+
+/// 结构化查询结果
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub row_count: usize,
+    /// 结果超过行数上限被截断
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+}
+
+impl QueryResult {
+    /// 按行数上限截断结果
+    pub fn new(columns: Vec<String>, mut rows: Vec<Vec<String>>, max_rows: usize) -> Self {
+        let truncated = rows.len() > max_rows;
+        rows.truncate(max_rows);
+        Self {
+            columns,
+            row_count: rows.len(),
+            rows,
+            truncated,
+            elapsed_ms: 0,
+        }
+    }
+}
+
+/// 校验 SQL 为单条只读语句。
+///
+/// 按词法扫描，字符串字面量与带引号的标识符中的内容不参与关键字判断；
+/// `replace(...)` 等同名函数调用不视为写操作。
+/// 执行的 SQL 按记号位置截取原文，运算符与数字字面量保持原样。
+pub fn check_read_only(sql: &str) -> Result<ReadOnlyQuery, AppError> {
+    let lexed = tokenize(sql)?;
+
+    let statements: Vec<&[(Token, Range<usize>)]> = lexed
+        .tokens
+        .split(|(token, _)| *token == Token::Semicolon)
+        .filter(|stmt| !stmt.is_empty())
+        .collect();
+    let statement = match statements.as_slice() {
+        [] => return Err(AppError::validation("SQL 不能为空")),
+        [statement] => *statement,
+        _ => return Err(AppError::forbidden_sql("只允许执行单条语句")),
+    };
+
+    let kind = match &statement[0].0 {
+        Token::Word(word) if word.eq_ignore_ascii_case("EXPLAIN") => StatementKind::Explain,
+        Token::Word(word)
+            if READ_ONLY_STARTS
+                .iter()
+                .any(|kw| word.eq_ignore_ascii_case(kw)) =>
+        {
+            StatementKind::Select
+        }
+        _ => return Err(AppError::forbidden_sql("只允许执行 SELECT 或 EXPLAIN 查询")),
+    };
+
+    for (idx, (token, _)) in statement.iter().enumerate() {
+        if let Token::Word(word) = token
+            && FORBIDDEN_KEYWORDS
+                .iter()
+                .any(|kw| word.eq_ignore_ascii_case(kw))
+            && statement.get(idx + 1).map(|(next, _)| next) != Some(&Token::OpenParen)
+        {
+            return Err(AppError::forbidden_sql(format!(
+                "查询中不允许使用 {}",
+                word.to_ascii_uppercase()
+            )));
+        }
+    }
+
+    // 截取首尾记号之间的原文，其中的注释替换为空格，保证执行内容与校验内容一致
+    let start = statement[0].1.start;
+    let end = statement[statement.len() - 1].1.end;
+    let mut text = String::with_capacity(end - start);
+    let mut pos = start;
+    for comment in lexed
+        .comments
+        .iter()
+        .filter(|comment| comment.start >= start && comment.end <= end)
+    {
+        text.push_str(&sql[pos..comment.start]);
+        text.push(' ');
+        pos = comment.end;
+    }
+    text.push_str(&sql[pos..end]);
+    Ok(ReadOnlyQuery { sql: text, kind })
+}
+
+/// 在阻塞线程池中执行查询并限制耗时。
+///
+/// 超时后立即返回错误；底层查询无法中断，会在后台执行完毕后丢弃结果。
+pub async fn run_with_timeout<T, F>(timeout: Duration, query: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(query)).await {
+        Ok(joined) => joined.map_err(AppError::internal)?,
+        Err(_) => Err(AppError::QueryTimeout(timeout.as_millis() as u64)),
+    }
+}
+
+/// 校验并执行只读查询：限制耗时与行数，返回结构化结果
+pub async fn run_read_only<F>(
+    sql: &str,
+    limits: QueryLimits,
+    execute: F,
+) -> Result<QueryResult, AppError>
+where
+    F: FnOnce(&str) -> Result<(Vec<String>, Vec<Vec<String>>), AppError> + Send + 'static,
+{
+    let query = check_read_only(sql)?;
+    let limited_sql = query.limited_sql(limits.max_rows);
+
+    let started = Instant::now();
+    let (columns, rows) = run_with_timeout(limits.timeout, move || execute(&limited_sql)).await?;
+
+    let mut result = QueryResult::new(columns, rows, limits.max_rows);
+    result.elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    /// 字符串字面量或带引号的标识符，保留原文
+    Quoted(String),
+    OpenParen,
+    Semicolon,
+    Other(String),
+}
+
+/// 词法扫描结果，范围均为原文中的字节区间
+struct Lexed {
+    tokens: Vec<(Token, Range<usize>)>,
+    comments: Vec<Range<usize>>,
+}
+
+/// 简单 SQL 词法扫描：跳过 `--`、`/* */` 注释，识别引号内容
+fn tokenize(sql: &str) -> Result<Lexed, AppError> {
+    let mut tokens = Vec::new();
+    let mut comments = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '-' if chars.peek().is_some_and(|&(_, next)| next == '-') => {
+                // 行注释不含结尾换行，替换后仍保留换行
+                let mut end = sql.len();
+                while let Some(&(idx, c)) = chars.peek() {
+                    if c == '\n' {
+                        end = idx;
+                        break;
+                    }
+                    chars.next();
+                }
+                comments.push(start..end);
+                continue;
+            }
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '*') => {
+                chars.next();
+                let mut prev = '\0';
+                let mut end = None;
+                for (idx, c) in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        end = Some(idx + 1);
+                        break;
+                    }
+                    prev = c;
+                }
+                let end = end.ok_or_else(|| AppError::validation("SQL 块注释未闭合"))?;
+                comments.push(start..end);
+                continue;
+            }
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let mut end = None;
+                while let Some((idx, c)) = chars.next() {
+                    if c == close {
+                        // 引号重复表示转义，如 'it''s'
+                        if close != ']' && chars.peek().is_some_and(|&(_, next)| next == close) {
+                            chars.next();
+                            continue;
+                        }
+                        end = Some(idx + c.len_utf8());
+                        break;
+                    }
+                }
+                let end =
+                    end.ok_or_else(|| AppError::validation("SQL 字符串或标识符引号未闭合"))?;
+                Token::Quoted(sql[start..end].to_string())
+            }
+            '(' => Token::OpenParen,
+            ';' => Token::Semicolon,
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(idx, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                        break;
+                    }
+                    end = idx + c.len_utf8();
+                    chars.next();
+                }
+                Token::Word(sql[start..end].to_string())
+            }
+            c => Token::Other(c.to_string()),
+        };
+        let end = chars.peek().map_or(sql.len(), |&(idx, _)| idx);
+        tokens.push((token, start..end));
+    }
+    Ok(Lexed { tokens, comments })
+}
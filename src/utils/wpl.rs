@@ -5,13 +5,18 @@ use orion_error::UvsReason;
 use serde::{Deserialize, Serialize};
 use wp_engine::sources::event_id::next_event_id;
 use wp_lang::{
-    AnnotationType, WparseError, WparseReason, WplCode, WplEvaluator, WplExpress, WplPackage,
-    WplStatementType,
+    AnnotationFunc, AnnotationType, WparseError, WparseReason, WplCode, WplEvaluator, WplExpress,
+    WplPackage, WplStatementType,
 };
 use wp_model_core::model::{DataField, DataRecord, DataType};
 use wp_parse_api::RawData;
 
-type RunParseProc = (WplExpress, Vec<AnnotationType>);
+/// 编译后的单条规则：规则名、表达式及其注解函数
+pub(crate) struct RuleItem {
+    pub name: String,
+    pub express: WplExpress,
+    pub funcs: Vec<AnnotationType>,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ParsedField {
@@ -36,40 +41,563 @@ pub fn record_to_fields(record: &DataRecord) -> Vec<ParsedField> {
         .collect()
 }
 
+/// 批量解析时单次请求允许的最大事件数
+pub const MAX_BATCH_EVENTS: usize = 10_000;
+
+/// 批量解析中单条事件的结果
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchRecordResult {
+    /// 事件序号（从 1 开始）
+    pub index: usize,
+    /// 事件在输入中的起始行号（从 1 开始）
+    pub line: usize,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<DataRecord>,
+    /// 字段在该条事件文本中的近似位置，见 [`record_spans`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<FieldSpan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 批量解析汇总结果
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchParseResult {
+    pub total: usize,
+    pub success: usize,
+    pub failed: usize,
+    pub items: Vec<BatchRecordResult>,
+}
+
+/// 字段在原始日志中的位置（左闭右开），同时给出字符与字节偏移
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpan {
+    /// 字段在 `DataRecord.items` 中的下标
+    pub index: usize,
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    /// 位置由字段值在日志中反查得到，而非评估器回传的消费区间
+    pub approximate: bool,
+}
+
+/// 估算解析字段在原始日志中的来源位置。
+///
+/// 评估器不回传消费区间，这里按字段顺序在游标之后反查字段值，结果均标记为近似位置。
+/// 只有字段值在剩余日志中按词边界恰好出现一次时才给出位置并推进游标；
+/// 找不到（时间、IP、JSON 等被管道规范化的值、标签、事件 ID）或出现多次（无法判断
+/// 取的是哪一处）的字段不给出位置，宁缺毋错。
+pub fn record_spans(data: &str, record: &DataRecord) -> Vec<FieldSpan> {
+    let mut spans = Vec::new();
+    let mut cursor = 0usize;
+    for (index, field) in record.items.iter().enumerate() {
+        let name = field.name.to_string();
+        if name == "wp_event_id" {
+            continue;
+        }
+        let value = field.value.to_string();
+        if value.is_empty() {
+            continue;
+        }
+        let mut found = word_matches(&data[cursor..], &value);
+        let (Some(offset), None) = (found.next(), found.next()) else {
+            continue;
+        };
+        let byte_start = cursor + offset;
+        let byte_end = byte_start + value.len();
+        let start = data[..byte_start].chars().count();
+        spans.push(FieldSpan {
+            index,
+            name,
+            start,
+            end: start + value.chars().count(),
+            byte_start,
+            byte_end,
+            approximate: true,
+        });
+        cursor = byte_end;
+    }
+    spans
+}
+
+/// `value` 在 `text` 中按词边界出现的字节位置：值以字母数字开头（结尾）时，
+/// 前（后）一个字符不能也是字母数字，避免把 `200` 定位到 `2001` 内部
+fn word_matches<'a>(text: &'a str, value: &'a str) -> impl Iterator<Item = usize> + 'a {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let word_start = value.chars().next().is_some_and(is_word);
+    let word_end = value.chars().next_back().is_some_and(is_word);
+    text.match_indices(value).filter_map(move |(pos, _)| {
+        let before = text[..pos].chars().next_back();
+        let after = text[pos + value.len()..].chars().next();
+        let bounded = !(word_start && before.is_some_and(is_word))
+            && !(word_end && after.is_some_and(is_word));
+        bounded.then_some(pos)
+    })
+}
+
+/// `wp_event_id` 生成策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventIdMode {
+    /// 与引擎一致，使用全局递增的事件 ID
+    #[default]
+    Auto,
+    /// 不追加 `wp_event_id`
+    Omit,
+    /// 使用固定起始值，批量解析时按事件序号递增，保证多次运行结果一致
+    Fixed(i64),
+}
+
+/// 调试解析输出选项
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ParseOptions {
+    pub event_id: EventIdMode,
+    /// 保留 `DataType::Ignore` 字段，便于检查被忽略的内容
+    pub keep_ignore: bool,
+    /// 是否追加规则标签字段
+    pub append_tags: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            event_id: EventIdMode::Auto,
+            keep_ignore: false,
+            append_tags: true,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// 计算第 `seq` 条事件（从 0 开始）的事件 ID
+    fn event_id(&self, seq: usize) -> Option<i64> {
+        match self.event_id {
+            EventIdMode::Auto => Some(next_event_id() as i64),
+            EventIdMode::Omit => None,
+            EventIdMode::Fixed(base) => Some(base + seq as i64),
+        }
+    }
+}
+
+/// 规则注解（`#[...]`）执行前后的字段变化
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AnnotationDiff {
+    /// 执行的注解函数数量
+    pub applied: usize,
+    pub before: Vec<ParsedField>,
+    pub after: Vec<ParsedField>,
+    /// 注解新增的字段名
+    pub added: Vec<String>,
+    /// 注解移除的字段名
+    pub removed: Vec<String>,
+}
+
+/// 单条事件的解析结果：命中规则、最终记录及注解执行情况
+#[derive(Debug, Clone)]
+pub struct ParseOutcome {
+    pub rule: String,
+    pub record: DataRecord,
+    /// 命中规则未声明注解时为 None
+    pub annotation: Option<AnnotationDiff>,
+}
+
 // 内部/其他模块使用：返回原始 DataRecord，供 OML 等后续处理
 pub fn warp_check_record(wpl: &str, data: &str) -> Result<DataRecord, AppError> {
+    warp_check_outcome(wpl, data, &ParseOptions::default()).map(|outcome| outcome.record)
+}
+
+/// 解析单条事件并返回完整结果（含命中规则与注解前后字段）
+pub fn warp_check_outcome(
+    wpl: &str,
+    data: &str,
+    options: &ParseOptions,
+) -> Result<ParseOutcome, AppError> {
+    let rule_items = parse_rule_items(wpl)?;
+    let evaluators = build_evaluators(&rule_items)?;
+    try_parse_with_rules(&rule_items, &evaluators, data, options, 0)
+}
+
+/// 批量解析：按分隔符切分输入，逐条事件独立解析并汇总成功/失败情况。
+///
+/// `delimiter` 为空时按行切分（兼容 `\r\n`），空白事件会被跳过。
+/// WPL 只编译一次，评估器在所有事件间复用。
+pub fn warp_check_batch(
+    wpl: &str,
+    data: &str,
+    delimiter: Option<&str>,
+    options: &ParseOptions,
+) -> Result<BatchParseResult, AppError> {
+    let events = split_events(data, delimiter);
+    if events.len() > MAX_BATCH_EVENTS {
+        return Err(AppError::validation(format!(
+            "批量解析事件数 {} 超过上限 {MAX_BATCH_EVENTS}",
+            events.len()
+        )));
+    }
+
+    let rule_items = parse_rule_items(wpl)?;
+    let evaluators = build_evaluators(&rule_items)?;
+
+    let mut items = Vec::with_capacity(events.len());
+    for (index, (line, event)) in events.into_iter().enumerate() {
+        let item = match try_parse_with_rules(&rule_items, &evaluators, event, options, index) {
+            Ok(outcome) => BatchRecordResult {
+                index: index + 1,
+                line,
+                success: true,
+                spans: record_spans(event, &outcome.record),
+                record: Some(outcome.record),
+                error: None,
+            },
+            Err(e) => BatchRecordResult {
+                index: index + 1,
+                line,
+                success: false,
+                record: None,
+                spans: Vec::new(),
+                error: Some(e.to_string()),
+            },
+        };
+        items.push(item);
+    }
+
+    let success = items.iter().filter(|item| item.success).count();
+    Ok(BatchParseResult {
+        total: items.len(),
+        success,
+        failed: items.len() - success,
+        items,
+    })
+}
+
+/// 规则匹配矩阵中的单条规则结果
+#[derive(Serialize, Debug, Clone)]
+pub struct RuleMatchItem {
+    /// 规则在包中的序号（从 1 开始）
+    pub index: usize,
+    pub name: String,
+    pub matched: bool,
+    /// 最深解析位置（字节偏移）：匹配成功时为规则消费的长度，失败时取自引擎错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 匹配成功时产出的字段数（不含忽略字段、标签与事件 ID）
+    pub field_count: usize,
+    /// 匹配成功但排在首个匹配规则之后，实际运行时不会生效
+    pub shadowed: bool,
+}
+
+/// 规则匹配矩阵：包内每条规则对同一输入的匹配情况
+#[derive(Serialize, Debug, Clone)]
+pub struct RuleMatrix {
+    pub rules: Vec<RuleMatchItem>,
+    /// 实际生效（首个匹配成功）的规则名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_match: Option<String>,
+    /// 是否存在多条规则同时匹配，规则顺序会影响结果
+    pub ambiguous: bool,
+}
+
+/// 诊断模式：对同一输入运行包内全部规则，不在首个匹配处停止。
+///
+/// 用于发现被前序规则遮蔽的规则以及依赖顺序的歧义匹配。
+pub fn warp_rule_matrix(wpl: &str, data: &str) -> Result<RuleMatrix, AppError> {
+    let rule_items = parse_rule_items(wpl)?;
+    let evaluators = build_evaluators(&rule_items)?;
+
+    let mut first_match: Option<String> = None;
+    let mut rules = Vec::with_capacity(rule_items.len());
+    for (index, (rule, evaluator)) in rule_items.iter().zip(&evaluators).enumerate() {
+        let raw = RawData::from_string(data.to_string());
+        let item = match evaluator.proc(raw, 0) {
+            Ok((tdc, residue)) => {
+                let shadowed = first_match.is_some();
+                if !shadowed {
+                    first_match = Some(rule.name.clone());
+                }
+                RuleMatchItem {
+                    index: index + 1,
+                    name: rule.name.clone(),
+                    matched: true,
+                    // 评估器返回未消费的剩余数据，已消费部分即为匹配深度
+                    depth: Some(data.len().saturating_sub(residue.len())),
+                    error: None,
+                    field_count: tdc
+                        .items
+                        .iter()
+                        .filter(|item| item.meta != DataType::Ignore)
+                        .count(),
+                    shadowed,
+                }
+            }
+            Err(e) => {
+                let depth = match e.reason() {
+                    WparseReason::Uvs(UvsReason::DataError(_, Some(pos))) => Some(*pos),
+                    _ => None,
+                };
+                RuleMatchItem {
+                    index: index + 1,
+                    name: rule.name.clone(),
+                    matched: false,
+                    depth,
+                    error: Some(e.to_string()),
+                    field_count: 0,
+                    shadowed: false,
+                }
+            }
+        };
+        rules.push(item);
+    }
+
+    let matched = rules.iter().filter(|item| item.matched).count();
+    Ok(RuleMatrix {
+        rules,
+        first_match,
+        ambiguous: matched > 1,
+    })
+}
+
+/// 规则匹配跟踪步骤类型
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    /// 一条规则的整体匹配结果
+    Rule,
+    /// 命中规则产出的字段
+    Field,
+}
+
+/// 规则匹配跟踪步骤结果
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceStatus {
+    Matched,
+    /// 匹配失败后回退，继续尝试下一条规则
+    Backtracked,
+    Failed,
+}
+
+/// 单个规则匹配跟踪步骤，位置均为字节偏移（左闭右开），与 [`RuleMatchItem::depth`] 一致
+#[derive(Serialize, Debug, Clone)]
+pub struct TraceStep {
+    /// 步骤序号（从 1 开始），即规则尝试与字段输出的先后顺序
+    pub step: usize,
+    pub kind: TraceKind,
+    pub rule: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>,
+    /// 本步骤消费的日志片段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consumed: Option<String>,
+    /// 位置为反查得到的近似值（字段步骤），见 [`record_spans`]
+    pub approximate: bool,
+    pub status: TraceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 规则匹配跟踪结果
+#[derive(Serialize, Debug, Clone)]
+pub struct WplTrace {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_rule: Option<String>,
+    pub steps: Vec<TraceStep>,
+}
+
+/// 规则级匹配跟踪：按规则尝试顺序记录每条规则的匹配结果，以及命中规则产出的字段。
+///
+/// 这不是分组、字段、管道粒度的逐步回放。评估器不对外暴露分组、管道与 alt/opt
+/// 回溯的中间过程，这里只到规则粒度：
+/// 规则失败时给出最深解析位置与原因并标记回退，成功时给出规则消费的区间；
+/// 随后按字段顺序输出各字段，位置为近似值（定位方式同 [`record_spans`]），
+/// 无法可靠定位的字段不给出位置。
+pub fn warp_trace(wpl: &str, data: &str) -> Result<WplTrace, AppError> {
+    let rule_items = parse_rule_items(wpl)?;
+    let evaluators = build_evaluators(&rule_items)?;
+    let slice = |start: usize, end: usize| -> String {
+        data[floor_char_boundary(data, start)..floor_char_boundary(data, end)].to_string()
+    };
+
+    let mut steps: Vec<TraceStep> = Vec::new();
+    let mut push = |mut step: TraceStep| {
+        step.step = steps.len() + 1;
+        steps.push(step);
+    };
+
+    let rule_cnt = rule_items.len();
+    for (index, (rule, evaluator)) in rule_items.iter().zip(&evaluators).enumerate() {
+        let raw = RawData::from_string(data.to_string());
+        match evaluator.proc(raw, 0) {
+            Ok((mut tdc, residue)) => {
+                tdc.items.retain(|item| item.meta != DataType::Ignore);
+                let record = DataRecord { items: tdc.items };
+                let spans = record_spans(data, &record);
+                // 评估器返回未消费的剩余数据，消费长度即字节偏移
+                let end = data.len().saturating_sub(residue.len());
+                push(TraceStep {
+                    step: 0,
+                    kind: TraceKind::Rule,
+                    rule: rule.name.clone(),
+                    name: rule.name.clone(),
+                    start: Some(0),
+                    end: Some(end),
+                    consumed: Some(slice(0, end)),
+                    approximate: false,
+                    status: TraceStatus::Matched,
+                    message: None,
+                });
+                for (field_idx, field) in record.items.iter().enumerate() {
+                    let span = spans.iter().find(|span| span.index == field_idx);
+                    push(TraceStep {
+                        step: 0,
+                        kind: TraceKind::Field,
+                        rule: rule.name.clone(),
+                        name: field.name.to_string(),
+                        start: span.map(|s| s.byte_start),
+                        end: span.map(|s| s.byte_end),
+                        consumed: span.map(|s| slice(s.byte_start, s.byte_end)),
+                        approximate: span.is_some(),
+                        status: TraceStatus::Matched,
+                        message: None,
+                    });
+                }
+                return Ok(WplTrace {
+                    success: true,
+                    matched_rule: Some(rule.name.clone()),
+                    steps,
+                });
+            }
+            Err(e) => {
+                let depth = match e.reason() {
+                    WparseReason::Uvs(UvsReason::DataError(_, Some(pos))) => Some(*pos),
+                    _ => None,
+                };
+                let status = if index + 1 < rule_cnt {
+                    TraceStatus::Backtracked
+                } else {
+                    TraceStatus::Failed
+                };
+                push(TraceStep {
+                    step: 0,
+                    kind: TraceKind::Rule,
+                    rule: rule.name.clone(),
+                    name: rule.name.clone(),
+                    start: Some(0),
+                    end: depth,
+                    consumed: depth.map(|pos| slice(0, pos)),
+                    approximate: false,
+                    status,
+                    message: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    Ok(WplTrace {
+        success: false,
+        matched_rule: None,
+        steps,
+    })
+}
+
+/// 将字节偏移截断到日志长度内，并向前取整到字符边界。
+///
+/// 引擎错误位置与评估器消费长度都是字节偏移，截取日志片段或换算字符序号前统一经此处理。
+fn floor_char_boundary(data: &str, offset: usize) -> usize {
+    let mut offset = offset.min(data.len());
+    while !data.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// 按分隔符切分事件，返回（起始行号, 事件文本）列表
+pub(crate) fn split_events<'a>(data: &'a str, delimiter: Option<&str>) -> Vec<(usize, &'a str)> {
+    let delimiter = match delimiter {
+        Some(d) if !d.is_empty() => d,
+        _ => "\n",
+    };
+
+    let mut events = Vec::new();
+    let mut line = 1usize;
+    for chunk in data.split(delimiter) {
+        // 事件前后的换行不计入内容，但需要计入行号
+        let leading = chunk.len() - chunk.trim_start_matches(['\r', '\n']).len();
+        let event = chunk.trim_matches(['\r', '\n']);
+        if !event.trim().is_empty() {
+            events.push((line + chunk[..leading].matches('\n').count(), event));
+        }
+        line += chunk.matches('\n').count() + delimiter.matches('\n').count();
+    }
+    events
+}
+
+/// 编译 WPL 文本并提取全部规则项
+pub(crate) fn parse_rule_items(wpl: &str) -> Result<Vec<RuleItem>, AppError> {
     // 保留解析错误中的换行与指示符，避免转义
-    let code = WplCode::build(PathBuf::from(""), wpl).map_err(AppError::wpl_parse)?;
-    let wpl_package = code.parse_pkg().map_err(AppError::wpl_parse)?;
-    // let wpl_package = parse_wpl_package(wpl)?;
+    let code = WplCode::build(PathBuf::from(""), wpl).map_err(|e| AppError::wpl_syntax(wpl, e))?;
+    let wpl_package = code.parse_pkg().map_err(|e| AppError::wpl_syntax(wpl, e))?;
     let rule_items = extract_rule_items(&wpl_package);
 
     if rule_items.is_empty() {
         return Err(AppError::wpl_parse_msg("WPL 中未找到任何规则"));
     }
-    try_parse_with_rules(rule_items, data)
+    Ok(rule_items)
+}
+
+/// 为每条规则构建评估器，供多次解析复用
+pub(crate) fn build_evaluators(rule_items: &[RuleItem]) -> Result<Vec<WplEvaluator>, AppError> {
+    rule_items
+        .iter()
+        .map(|rule| WplEvaluator::from(&rule.express, None).map_err(AppError::wpl_parse))
+        .collect()
 }
 
 /// 尝试用规则列表解析数据
-fn try_parse_with_rules(rule_items: Vec<RunParseProc>, data: &str) -> Result<DataRecord, AppError> {
+pub(crate) fn try_parse_with_rules(
+    rule_items: &[RuleItem],
+    evaluators: &[WplEvaluator],
+    data: &str,
+    options: &ParseOptions,
+    seq: usize,
+) -> Result<ParseOutcome, AppError> {
     let mut max_depth = 0;
     let mut best_error = None;
     let rule_cnt = rule_items.len();
     let mut best_wpl = 1;
-    for (index, (vm_unit, _funcs)) in rule_items.iter().enumerate() {
+    for (index, (rule, evaluator)) in rule_items.iter().zip(evaluators).enumerate() {
         let is_last = index == rule_cnt - 1;
-        let evaluator = WplEvaluator::from(vm_unit, None).map_err(AppError::wpl_parse)?;
         let raw = RawData::from_string(data.to_string());
         match evaluator.proc(raw, 0) {
             Ok((mut tdc, _pipeline)) => {
-                if let Some(tags) = vm_unit.tags.clone() {
+                let annotation = apply_annotations(rule, data, &mut tdc)?;
+                if options.append_tags
+                    && let Some(tags) = rule.express.tags.clone()
+                {
                     for tag in tags.export_tags() {
                         tdc.append(DataField::from_chars(tag.key.clone(), tag.val.clone()));
                     }
                 }
-                tdc.append(DataField::from_digit("wp_event_id", next_event_id() as i64));
-                tdc.items.retain(|item| item.meta != DataType::Ignore);
-                return Ok(DataRecord { items: tdc.items });
+                if let Some(event_id) = options.event_id(seq) {
+                    tdc.append(DataField::from_digit("wp_event_id", event_id));
+                }
+                if !options.keep_ignore {
+                    tdc.items.retain(|item| item.meta != DataType::Ignore);
+                }
+                return Ok(ParseOutcome {
+                    rule: rule.name.clone(),
+                    record: DataRecord { items: tdc.items },
+                    annotation,
+                });
             }
             Err(e) => {
                 // 记录解析深度最高的错误
@@ -105,8 +633,54 @@ fn try_parse_with_rules(rule_items: Vec<RunParseProc>, data: &str) -> Result<Dat
     ))
 }
 
+/// 按引擎流水线顺序执行规则注解函数，并记录执行前后的字段变化
+fn apply_annotations(
+    rule: &RuleItem,
+    data: &str,
+    record: &mut DataRecord,
+) -> Result<Option<AnnotationDiff>, AppError> {
+    if rule.funcs.is_empty() {
+        return Ok(None);
+    }
+
+    let visible = |record: &DataRecord| DataRecord {
+        items: record
+            .items
+            .iter()
+            .filter(|item| item.meta != DataType::Ignore)
+            .cloned()
+            .collect(),
+    };
+    let before = record_to_fields(&visible(record));
+
+    let raw = RawData::from_string(data.to_string());
+    for func in rule.funcs.iter() {
+        func.proc(&raw, record).map_err(AppError::wpl_parse)?;
+    }
+    let after = record_to_fields(&visible(record));
+
+    let added = after
+        .iter()
+        .filter(|f| !before.iter().any(|b| b.name == f.name))
+        .map(|f| f.name.clone())
+        .collect();
+    let removed = before
+        .iter()
+        .filter(|b| !after.iter().any(|f| f.name == b.name))
+        .map(|b| b.name.clone())
+        .collect();
+
+    Ok(Some(AnnotationDiff {
+        applied: rule.funcs.len(),
+        before,
+        after,
+        added,
+        removed,
+    }))
+}
+
 /// 从 WPL 包中提取规则项
-fn extract_rule_items(wpl_package: &WplPackage) -> Vec<RunParseProc> {
+fn extract_rule_items(wpl_package: &WplPackage) -> Vec<RuleItem> {
     let mut rule_pairs = Vec::with_capacity(wpl_package.rules.len());
 
     for rule in wpl_package.rules.iter() {
@@ -114,14 +688,21 @@ fn extract_rule_items(wpl_package: &WplPackage) -> Vec<RunParseProc> {
             WplStatementType::Express(code) => code.clone(),
         };
         let funcs = AnnotationType::convert(rule.statement.tags());
-        rule_pairs.push((rule_obj, funcs));
+        rule_pairs.push(RuleItem {
+            name: rule.name().to_string().trim().to_string(),
+            express: rule_obj,
+            funcs,
+        });
     }
     rule_pairs
 }
 
 /// 构造更友好的日志位置提示，帮助用户快速定位解析中断点
+///
+/// `depth` 为引擎给出的字节偏移，这里换算为字符序号后再截取上下文。
 fn build_best_match_hint(data: &str, depth: usize, rule_name: usize) -> String {
     let chars: Vec<char> = data.chars().collect();
+    let depth = data[..floor_char_boundary(data, depth)].chars().count();
     if chars.is_empty() {
         return format!(
             "rule {rule_name} Achieved the best match, but the log is empty and the location cannot be determined."
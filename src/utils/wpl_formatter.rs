@@ -1,6 +1,36 @@
-/// WPL 代码格式化器：通过轻量词法扫描与缩进规则生成稳定输出。
+use crate::error::AppError;
+use crate::utils::format_options::{FormatOptions, TrailingComma, wrap_pipes};
+use crate::utils::oml::{at_word_start, raw_func_len};
+use std::fmt;
+use std::path::PathBuf;
+use wp_lang::{WplCode, WplStatementType};
+
+/// 参数为字段列表、需参与排版的函数（分组与带子字段的类型）。
+///
+/// 其余 `name(...)` 一律按原样函数保留内部的管道、逗号与括号，
+/// 引擎新增函数时无需同步维护列表。
+pub(crate) const LAYOUT_FUNCS: &[&str] = &["alt", "opt", "some_of", "seq", "json"];
+
+/// 原样函数块 `name(...)` 的长度；函数名属于 [`LAYOUT_FUNCS`] 或括号未闭合时返回 None。
+///
+/// 调用方需保证 `rest` 位于词首。
+pub(crate) fn raw_call_len(rest: &str) -> Option<usize> {
+    let name_len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let name = &rest[..name_len];
+    if name.is_empty() || LAYOUT_FUNCS.contains(&name) || !rest[name_len..].starts_with('(') {
+        return None;
+    }
+    raw_func_len(rest, &[name])
+}
+
+/// WPL 代码格式化器：排版基于轻量词法扫描，[`format`](Self::format) 在排版前后
+/// 交由引擎解析，保证只调整布局、不改变规则。
+///
+/// 排版没有直接由解析树输出：引擎的解析树不保留注释与源码位置，无法把注释放回原处。
 pub struct WplFormatter {
-    indent: usize,
+    options: FormatOptions,
 }
 
 impl Default for WplFormatter {
@@ -12,36 +42,56 @@ impl Default for WplFormatter {
 impl WplFormatter {
     /// 默认 4 空格缩进。
     pub fn new() -> Self {
-        Self { indent: 4 }
+        Self::with_options(FormatOptions::default())
     }
 
     /// 自定义缩进宽度（单位：空格）。
     pub fn with_indent(indent: usize) -> Self {
-        Self {
-            indent: indent.max(1),
+        Self::with_options(FormatOptions {
+            indent_width: indent.max(1),
+            ..FormatOptions::default()
+        })
+    }
+
+    pub fn with_options(options: FormatOptions) -> Self {
+        Self { options }
+    }
+
+    /// 格式化 WPL 源码。
+    ///
+    /// 源码无法被引擎解析时返回带源码位置的语法诊断；排版结果会再次解析，
+    /// 比较包名及每条规则的名称、注解与完整内容，与原文不一致时报错，避免格式化改写规则。
+    pub fn format(&self, content: &str) -> Result<String, AppError> {
+        let parsed = parse_package(content)?;
+        let formatted = self
+            .layout(content)
+            .map_err(|e| AppError::internal(format!("WPL 格式化失败: {e}")))?;
+        match parse_package(&formatted) {
+            Ok(reparsed) if reparsed == parsed => Ok(formatted),
+            Ok(_) => Err(AppError::internal(
+                "WPL 格式化结果的包名、注解或规则与原文不一致",
+            )),
+            Err(e) => Err(AppError::internal(format!(
+                "WPL 格式化结果无法通过解析: {e}"
+            ))),
         }
     }
 
-    /// 出错时返回原文，避免影响调用方。
+    /// 仅做排版、不校验语法与规则，出错时返回原文；需要发现格式化问题的场景请使用
+    /// [`format`](Self::format)。
     pub fn format_content(&self, content: &str) -> String {
-        match self.format(content) {
+        match self.layout(content) {
             Ok(v) => v,
             Err(_) => content.to_string(),
         }
     }
 
-    fn format(&self, content: &str) -> Result<String, WplFormatError> {
+    fn layout(&self, content: &str) -> Result<String, WplFormatError> {
         let normalized = content.replace("\r\n", "\n").replace('\r', "\n");
         let mut out = String::with_capacity(normalized.len() + 64);
         let chars: Vec<char> = normalized.chars().collect();
-
-        const RAW_FUNCS: &[&str] = &[
-            "symbol",
-            "f_chars_not_has",
-            "f_chars_has",
-            "kv",
-            "f_chars_in",
-        ];
+        // 字符下标对应的字节偏移，供按字符串切片识别原样函数
+        let offsets: Vec<usize> = normalized.char_indices().map(|(idx, _)| idx).collect();
 
         let mut i = 0usize;
         let mut indent = 0usize;
@@ -79,6 +129,36 @@ impl WplFormatter {
                 continue;
             }
 
+            // 注释原样保留：行注释延伸到行尾，块注释保持内部换行
+            if c == '/' && chars.get(i + 1) == Some(&'/') {
+                let len = chars[i..]
+                    .iter()
+                    .position(|&ch| ch == '\n')
+                    .unwrap_or(chars.len() - i);
+                let comment: String = chars[i..i + len].iter().collect();
+                self.write_indent_if_needed(start_of_line, indent, &mut out)?;
+                if !start_of_line && !out.ends_with(' ') {
+                    out.push(' ');
+                }
+                out.push_str(comment.trim_end());
+                out.push('\n');
+                i += len;
+                start_of_line = true;
+                continue;
+            }
+            if c == '/' && chars.get(i + 1) == Some(&'*') {
+                let len = chars[i + 2..]
+                    .windows(2)
+                    .position(|w| w == ['*', '/'])
+                    .map(|end| end + 4)
+                    .ok_or(WplFormatError::UnclosedComment)?;
+                self.write_indent_if_needed(start_of_line, indent, &mut out)?;
+                out.extend(&chars[i..i + len]);
+                i += len;
+                start_of_line = false;
+                continue;
+            }
+
             // 注解块 #[...] 直接压缩为单行
             if c == '#' && i + 1 < chars.len() && chars[i + 1] == '[' {
                 let (ann, consumed) = self.read_bracket_block(&chars[i..], '[', ']')?;
@@ -89,9 +169,8 @@ impl WplFormatter {
                         .collect::<Vec<_>>()
                         .join(" "),
                 );
-                out.push('\n');
                 i += consumed;
-                start_of_line = true;
+                start_of_line = self.end_line(&chars, i, &mut out);
                 continue;
             }
 
@@ -120,14 +199,15 @@ impl WplFormatter {
                 continue;
             }
 
-            // 自定义 raw 函数：内部内容按原样保留，不解析管道/逗号
-            if let Some(name_len) = self.starts_with_raw_func(&chars, i, RAW_FUNCS)
-                && let Some((block, consumed)) = self.read_raw_func_block(&chars[i..], name_len)
+            // 原样函数：内部内容按原样保留，不解析管道/逗号
+            if at_word_start(&normalized, offsets[i])
+                && let Some(len) = raw_call_len(&normalized[offsets[i]..])
             {
+                let block = &normalized[offsets[i]..offsets[i] + len];
                 self.write_indent_if_needed(start_of_line, indent, &mut out)?;
-                out.push_str(&block);
+                out.push_str(block);
                 start_of_line = false;
-                i += consumed;
+                i += block.chars().count();
                 continue;
             }
 
@@ -144,10 +224,9 @@ impl WplFormatter {
                 '{' => {
                     self.write_indent_if_needed(start_of_line, indent, &mut out)?;
                     out.push('{');
-                    out.push('\n');
                     indent += 1;
-                    start_of_line = true;
                     i += 1;
+                    start_of_line = self.end_line(&chars, i, &mut out);
                 }
                 '}' => {
                     indent = indent.saturating_sub(1);
@@ -156,9 +235,8 @@ impl WplFormatter {
                     }
                     self.write_indent_if_needed(true, indent, &mut out)?;
                     out.push('}');
-                    out.push('\n');
-                    start_of_line = true;
                     i += 1;
+                    start_of_line = self.end_line(&chars, i, &mut out);
                 }
                 '(' => {
                     if let Some((inner, consumed)) = self.peek_block(&chars[i..], '(', ')')
@@ -175,13 +253,15 @@ impl WplFormatter {
                     }
                     self.write_indent_if_needed(start_of_line, indent, &mut out)?;
                     out.push('(');
-                    out.push('\n');
                     indent += 1;
-                    start_of_line = true;
                     i += 1;
+                    start_of_line = self.end_line(&chars, i, &mut out);
                 }
                 ')' => {
                     indent = indent.saturating_sub(1);
+                    if self.apply_trailing_comma(&mut out) {
+                        start_of_line = false;
+                    }
                     if !start_of_line {
                         out.push('\n');
                     }
@@ -192,9 +272,8 @@ impl WplFormatter {
                 }
                 ',' => {
                     out.push(',');
-                    out.push('\n');
-                    start_of_line = true;
                     i += 1;
+                    start_of_line = self.end_line(&chars, i, &mut out);
                 }
                 '|' => {
                     self.write_indent_if_needed(start_of_line, indent, &mut out)?;
@@ -234,7 +313,7 @@ impl WplFormatter {
         if !final_out.ends_with('\n') {
             final_out.push('\n');
         }
-        Ok(final_out)
+        Ok(wrap_pipes(&final_out, &self.options, false))
     }
 
     fn write_indent_if_needed(
@@ -244,13 +323,36 @@ impl WplFormatter {
         buf: &mut String,
     ) -> Result<(), WplFormatError> {
         if start_of_line {
-            for _ in 0..indent {
-                buf.push_str(&" ".repeat(self.indent));
-            }
+            buf.push_str(&self.options.indent_unit().repeat(indent));
         }
         Ok(())
     }
 
+    /// 多行分组闭合前按策略调整最后一项的逗号，返回是否改写了行尾。
+    ///
+    /// 空分组与以注释结尾的分组保持原样；`\,` 是分隔符声明而非尾随逗号。
+    fn apply_trailing_comma(&self, buf: &mut String) -> bool {
+        let end = buf.trim_end().len();
+        let content = &buf[..end];
+        let last_line = content.rsplit('\n').next().unwrap_or("");
+        if content.ends_with('(') || last_line.contains("//") || last_line.ends_with("*/") {
+            return false;
+        }
+        let has_comma = content.ends_with(',') && !content.ends_with("\\,");
+        match self.options.trailing_comma {
+            TrailingComma::Always if !has_comma => {
+                buf.truncate(end);
+                buf.push(',');
+                true
+            }
+            TrailingComma::Never if has_comma => {
+                buf.truncate(end - 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn read_string(&self, input: &[char]) -> Result<(String, usize), WplFormatError> {
         let mut out = String::new();
         let mut escaped = false;
@@ -404,67 +506,84 @@ impl WplFormatter {
         false
     }
 
-    /// 检测是否匹配 raw 函数名并紧跟 '('，返回函数名长度。
-    fn starts_with_raw_func(&self, input: &[char], idx: usize, names: &[&str]) -> Option<usize> {
-        for name in names {
-            let pat: Vec<char> = name.chars().chain(['(']).collect();
-            if idx + pat.len() > input.len() {
-                continue;
-            }
-            if input[idx..idx + pat.len()]
-                .iter()
-                .zip(pat.iter())
-                .all(|(a, b)| a == b)
-            {
-                return Some(name.len());
-            }
+    /// 结构字符后换行；同一行紧跟行注释时保留在行尾，返回是否已换行。
+    fn end_line(&self, input: &[char], start: usize, buf: &mut String) -> bool {
+        if self.trailing_comment(input, start) {
+            return false;
         }
-        None
+        buf.push('\n');
+        true
     }
 
-    /// 读取 raw 函数块（如 symbol/自定义函数），内部内容原样保留（含管道/逗号/转义）。
-    fn read_raw_func_block(&self, input: &[char], name_len: usize) -> Option<(String, usize)> {
-        let mut out = String::new();
-        let mut depth = 0i32;
-        let mut in_str = false;
-        let mut escaped = false;
-        let mut seen_func = false;
+    /// `start` 之后在同一行内（仅隔空格/制表符）是否为行注释。
+    fn trailing_comment(&self, input: &[char], start: usize) -> bool {
+        let rest = &input[start.min(input.len())..];
+        let pos = rest.iter().position(|ch| !matches!(ch, ' ' | '\t'));
+        pos.is_some_and(|pos| rest[pos..].starts_with(&['/', '/']))
+    }
+}
 
-        for (idx, ch) in input.iter().enumerate() {
-            out.push(*ch);
-            // 首个 '(' 之前确保匹配函数名，避免误读相似前缀
-            if !seen_func && idx + 1 == name_len {
-                seen_func = true;
-            }
-            if escaped {
-                escaped = false;
-                continue;
-            }
-            if *ch == '\\' {
-                escaped = true;
-                continue;
-            }
-            if *ch == '"' {
-                in_str = !in_str;
-                continue;
-            }
-            if in_str {
-                continue;
-            }
-            if *ch == '(' {
-                depth += 1;
-            } else if *ch == ')' {
-                depth -= 1;
-                if depth == 0 {
-                    return Some((out, idx + 1));
-                }
+/// 排版前后需保持一致的解析结果
+#[derive(Debug, PartialEq, Eq)]
+struct ParsedPackage {
+    name: String,
+    rules: Vec<ParsedRule>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParsedRule {
+    name: String,
+    /// 注解导出的标签（`key=value`）
+    tags: Vec<String>,
+    /// 引擎 `Display` 输出，折叠空白
+    body: String,
+}
+
+/// 由引擎解析 WPL，提取包名与各规则的名称、注解及完整内容，供格式化前后比对
+fn parse_package(content: &str) -> Result<ParsedPackage, AppError> {
+    let code =
+        WplCode::build(PathBuf::from(""), content).map_err(|e| AppError::wpl_syntax(content, e))?;
+    let package = code
+        .parse_pkg()
+        .map_err(|e| AppError::wpl_syntax(content, e))?;
+    let rules = package
+        .rules
+        .iter()
+        .map(|rule| {
+            let WplStatementType::Express(express) = &rule.statement;
+            ParsedRule {
+                name: rule.name().to_string().trim().to_string(),
+                tags: express
+                    .tags
+                    .iter()
+                    .flat_map(|tags| tags.export_tags())
+                    .map(|tag| format!("{}={}", tag.key, tag.val))
+                    .collect(),
+                body: rule
+                    .to_string()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
             }
-        }
-        None
-    }
+        })
+        .collect();
+    Ok(ParsedPackage {
+        name: package.name().to_string().trim().to_string(),
+        rules,
+    })
 }
 
 #[derive(Debug)]
 pub enum WplFormatError {
     UnclosedLiteral,
+    UnclosedComment,
+}
+
+impl fmt::Display for WplFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WplFormatError::UnclosedLiteral => write!(f, "存在未闭合的字面量或括号"),
+            WplFormatError::UnclosedComment => write!(f, "存在未闭合的块注释"),
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use actix_web::test;
+use actix_web::{App, body::to_bytes, http::StatusCode};
+use serde::Deserialize;
+
+use wp_editor::api;
+
+#[derive(Deserialize)]
+struct FormatCheckResponse {
+    success: bool,
+    formatted: bool,
+    diff: String,
+    content: String,
+}
+
+const WPL_RULE: &str = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+// 格式检查返回差异与格式化结果，格式化结果再次检查应无差异。
+#[actix_web::test]
+async fn wpl_format_check_reports_diff() {
+    let app = test::init_service(App::new().service(api::wpl_format_check)).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/debug/wpl/format/check")
+        .set_payload(WPL_RULE)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = to_bytes(resp.into_body()).await.unwrap();
+    let check: FormatCheckResponse = serde_json::from_slice(&body).unwrap();
+    assert!(check.success);
+    assert!(!check.formatted);
+    assert!(check.diff.contains("+++ b/rule.wpl"), "{}", check.diff);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/debug/wpl/format/check?indent_width=4")
+        .set_payload(check.content.clone())
+        .send_request(&app)
+        .await;
+    let body = to_bytes(resp.into_body()).await.unwrap();
+    let again: FormatCheckResponse = serde_json::from_slice(&body).unwrap();
+    assert!(again.formatted);
+    assert!(again.diff.is_empty());
+    assert_eq!(again.content, check.content);
+}
+
+// 无法解析的规则返回语法错误，而不是原样返回。
+#[actix_web::test]
+async fn wpl_format_reports_syntax_error() {
+    let app = test::init_service(App::new().service(api::wpl_format)).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/debug/wpl/format")
+        .set_payload("package demo {\n    rule nginx { (digit:id }\n}\n")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = to_bytes(resp.into_body()).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["success"], false);
+    assert_eq!(json["error"]["code"], "WPL_PARSE_ERROR");
+}
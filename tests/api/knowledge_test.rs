@@ -0,0 +1,83 @@
+use actix_web::test;
+use actix_web::{App, body::to_bytes, http::StatusCode};
+
+use wp_editor::api;
+
+// 未配置数据库时，知识库接口应返回 503 与明确的错误码，而不是让 worker 崩溃
+#[actix_web::test]
+async fn api_db_list_without_database_returns_unavailable() {
+    let app = test::init_service(App::new().service(api::knowledge::get_db_list)).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/db_list")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let body_bytes = to_bytes(resp.into_body()).await.expect("read body failed");
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_bytes).expect("parse error response failed");
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error"]["code"], "DATABASE_UNAVAILABLE");
+}
+
+// 参数校验先于数据库访问，非法名称与缺失建表 SQL 均返回 400
+#[actix_web::test]
+async fn api_create_config_validates_input() {
+    let app = test::init_service(App::new().service(api::knowledge::create_config)).await;
+
+    let cases = [
+        serde_json::json!({ "file_name": "bad/name", "create_sql": "CREATE TABLE t (id INT)" }),
+        serde_json::json!({ "file_name": "ip_zone" }),
+        serde_json::json!({
+            "file_name": "ip_zone",
+            "create_sql": "CREATE TABLE ip_zone (ip TEXT)",
+            "config_content": "[table",
+        }),
+        serde_json::json!({
+            "file_name": "ip_zone",
+            "create_sql": "CREATE TABLE ip_zone (ip TEXT)",
+            "data_content": "ip\n10.0.0.1\n",
+        }),
+    ];
+    for payload in cases {
+        let resp = test::TestRequest::post()
+            .uri("/api/knowledge/configs")
+            .set_json(&payload)
+            .send_request(&app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "payload: {payload}");
+
+        let body_bytes = to_bytes(resp.into_body()).await.expect("read body failed");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body_bytes).expect("parse error response failed");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    }
+}
+
+// 按名称访问配置的接口同样先校验名称，非法名称不访问数据库直接返回 400
+#[actix_web::test]
+async fn api_config_endpoints_validate_file_name() {
+    let app = test::init_service(
+        App::new()
+            .service(api::knowledge::update_config_active)
+            .service(api::knowledge::delete_config),
+    )
+    .await;
+
+    let requests = [
+        test::TestRequest::put()
+            .uri("/api/knowledge/configs/bad.name/active")
+            .set_json(serde_json::json!({ "is_active": true })),
+        test::TestRequest::delete().uri("/api/knowledge/configs/bad.name"),
+    ];
+    for req in requests {
+        let resp = req.send_request(&app).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body_bytes = to_bytes(resp.into_body()).await.expect("read body failed");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body_bytes).expect("parse error response failed");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    }
+}
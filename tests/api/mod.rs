@@ -1 +1,3 @@
+pub mod debug_test;
+pub mod knowledge_test;
 pub mod system_test;
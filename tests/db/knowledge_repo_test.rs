@@ -0,0 +1,130 @@
+use tempfile::TempDir;
+use wp_editor::db::{DbPool, KnowledgeConfigRepo, KnowledgeHistoryRepo, NewKnowledgeConfig};
+
+fn new_config(file_name: &str, data: &str) -> NewKnowledgeConfig {
+    NewKnowledgeConfig {
+        file_name: file_name.to_string(),
+        config_content: None,
+        create_sql: Some(format!("CREATE TABLE {file_name} (ip TEXT)")),
+        insert_sql: Some(format!("INSERT INTO {file_name} (ip) VALUES (?1)")),
+        data_content: Some(data.to_string()),
+    }
+}
+
+// SQLite 文件库：自动创建数据目录，迁移可重复执行
+#[tokio::test]
+async fn sqlite_pool_creates_file_and_migrates() {
+    let dir = TempDir::new().unwrap();
+    let url = format!("sqlite://{}/data/wp-editor.db", dir.path().display());
+
+    let pool = DbPool::new(&url, 4, 1).await.expect("连接 SQLite 失败");
+    assert!(dir.path().join("data/wp-editor.db").exists());
+    pool.test_connection().await.unwrap();
+
+    let pending = pool.migration_status().await.unwrap().pending;
+    assert!(!pending.is_empty());
+    assert_eq!(pool.migrate().await.unwrap(), pending);
+    assert!(pool.migration_status().await.unwrap().pending.is_empty());
+    assert!(pool.migrate().await.unwrap().is_empty());
+}
+
+// 配置仓储与修订历史在 SQLite 上的完整流程
+#[tokio::test]
+async fn sqlite_knowledge_repo_records_revisions() {
+    let pool = DbPool::new("sqlite::memory:", 1, 1).await.unwrap();
+    pool.migrate().await.unwrap();
+
+    let repo = KnowledgeConfigRepo::new(pool.inner());
+    let history = KnowledgeHistoryRepo::new(pool.inner());
+    repo.create(new_config("ip_owner", "ip\n10.0.0.1\n"))
+        .await
+        .unwrap();
+
+    let config = repo.find_by_file_name("ip_owner").await.unwrap().unwrap();
+    assert_eq!(
+        history
+            .record(&config, "create", Some("alice".to_string()))
+            .await
+            .unwrap(),
+        1
+    );
+
+    repo.update_data_content("ip_owner", "ip\n10.0.0.2\n".to_string())
+        .await
+        .unwrap();
+    let config = repo.find_by_file_name("ip_owner").await.unwrap().unwrap();
+    assert_eq!(history.record(&config, "upload", None).await.unwrap(), 2);
+
+    let revisions = history.find_by_file_name("ip_owner").await.unwrap();
+    assert_eq!(
+        revisions.iter().map(|r| r.revision).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+    let first = history.find_revision("ip_owner", 1).await.unwrap().unwrap();
+    assert_eq!(first.data_content.as_deref(), Some("ip\n10.0.0.1\n"));
+    assert_eq!(first.author.as_deref(), Some("alice"));
+
+    repo.update_active("ip_owner", false).await.unwrap();
+    assert!(repo.get_status_list().await.unwrap().is_empty());
+
+    repo.delete_by_file_name("ip_owner").await.unwrap();
+    assert!(repo.find_all().await.unwrap().is_empty());
+}
+
+// 变更与修订在同一事务中：未提交的事务丢弃时两者一起回滚
+#[tokio::test]
+async fn sqlite_knowledge_revision_follows_transaction() {
+    let pool = DbPool::new("sqlite::memory:", 1, 1).await.unwrap();
+    pool.migrate().await.unwrap();
+
+    let txn = pool.begin().await.unwrap();
+    KnowledgeConfigRepo::new(&txn)
+        .create(new_config("ip_owner", "ip\n10.0.0.1\n"))
+        .await
+        .unwrap();
+    let config = KnowledgeConfigRepo::new(&txn)
+        .lock("ip_owner")
+        .await
+        .unwrap()
+        .unwrap();
+    let history = KnowledgeHistoryRepo::new(&txn);
+    assert_eq!(history.record(&config, "create", None).await.unwrap(), 1);
+    txn.commit().await.unwrap();
+
+    let txn = pool.begin().await.unwrap();
+    KnowledgeConfigRepo::new(&txn)
+        .update_data_content("ip_owner", "ip\n10.0.0.2\n".to_string())
+        .await
+        .unwrap();
+    let config = KnowledgeConfigRepo::new(&txn)
+        .lock("ip_owner")
+        .await
+        .unwrap()
+        .unwrap();
+    let history = KnowledgeHistoryRepo::new(&txn);
+    assert_eq!(history.record(&config, "upload", None).await.unwrap(), 2);
+    drop(txn);
+
+    let repo = KnowledgeConfigRepo::new(pool.inner());
+    let config = repo.find_by_file_name("ip_owner").await.unwrap().unwrap();
+    assert_eq!(config.data_content.as_deref(), Some("ip\n10.0.0.1\n"));
+    let revisions = KnowledgeHistoryRepo::new(pool.inner())
+        .find_by_file_name("ip_owner")
+        .await
+        .unwrap();
+    assert_eq!(
+        revisions.iter().map(|r| r.revision).collect::<Vec<_>>(),
+        vec![1]
+    );
+}
+
+// 知识库名称由迁移建立唯一索引，重复插入直接失败
+#[tokio::test]
+async fn sqlite_knowledge_file_name_is_unique() {
+    let pool = DbPool::new("sqlite::memory:", 1, 1).await.unwrap();
+    pool.migrate().await.unwrap();
+
+    let repo = KnowledgeConfigRepo::new(pool.inner());
+    repo.create(new_config("ip_owner", "ip\n")).await.unwrap();
+    assert!(repo.create(new_config("ip_owner", "ip\n")).await.is_err());
+}
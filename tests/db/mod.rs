@@ -0,0 +1 @@
+pub mod knowledge_repo_test;
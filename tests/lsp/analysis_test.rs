@@ -0,0 +1,147 @@
+use lsp_types::{FoldingRangeKind, NumberOrString, Position, Range, SymbolKind, Uri};
+use std::str::FromStr;
+use wp_editor::lsp::analysis::{self, Lang, LineIndex};
+use wp_editor::lsp::symbols::{document_symbols, folding_ranges};
+use wp_editor::utils::diagnostic::{Diagnostic, OML_SYNTAX};
+
+const WPL: &str = "\
+package /example/simple {
+    rule nginx {
+        (ip:sip, chars:rule)
+    }
+    /* 备用规则
+       暂未启用 */
+    rule backup { (digit:id) }
+}
+";
+
+const OML: &str = "\
+name : /oml/example/simple
+rule : /example/simple*
+---
+src_ip : ip = take(sip) ;
+block = match read(kind) {
+    chars(A) => digit(1) ;
+    _ => digit(0) ;
+}
+site = chars(http://example.com/;a) ;
+";
+
+// 语言识别：languageId 优先，其次按扩展名
+#[test]
+fn lang_detects_language_id_and_extension() {
+    assert_eq!(Lang::from_language_id("WPL"), Some(Lang::Wpl));
+    let uri = Uri::from_str("file:///repo/models/oml/demo.oml").unwrap();
+    assert_eq!(Lang::from_uri(&uri), Some(Lang::Oml));
+    let uri = Uri::from_str("file:///repo/README.md").unwrap();
+    assert_eq!(Lang::from_uri(&uri), None);
+}
+
+// 列号按 UTF-16 码元计算
+#[test]
+fn line_index_uses_utf16_columns() {
+    let text = "a\n中文x\n";
+    let index = LineIndex::new(text);
+    assert_eq!(index.position(0), Position::new(0, 0));
+    assert_eq!(index.position(text.find('x').unwrap()), Position::new(1, 2));
+    assert_eq!(index.end(), Position::new(2, 0));
+    assert_eq!(
+        index.line_range(1),
+        Range::new(Position::new(1, 0), Position::new(1, 3))
+    );
+}
+
+// WPL 符号：规则嵌套在包内，规则体内名为 rule 的字段不是声明
+#[test]
+fn wpl_symbols_nest_rules_in_package() {
+    let symbols = document_symbols(Lang::Wpl, WPL);
+    assert_eq!(symbols.len(), 1);
+    let package = &symbols[0];
+    assert_eq!(package.name, "/example/simple");
+    assert_eq!(package.kind, SymbolKind::PACKAGE);
+    assert_eq!(package.range.end, Position::new(7, 1));
+
+    let rules: Vec<_> = package.children.as_ref().unwrap().iter().collect();
+    let names: Vec<_> = rules.iter().map(|rule| rule.name.as_str()).collect();
+    assert_eq!(names, vec!["nginx", "backup"]);
+    assert_eq!(rules[0].selection_range.start, Position::new(1, 9));
+}
+
+// OML 符号：模型名为顶层，目标字段为子项，match 块可省略分号
+#[test]
+fn oml_symbols_list_target_fields() {
+    let symbols = document_symbols(Lang::Oml, OML);
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "/oml/example/simple");
+    assert_eq!(symbols[0].kind, SymbolKind::MODULE);
+
+    let fields = symbols[0].children.as_ref().unwrap();
+    let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["src_ip", "block", "site"]);
+    assert_eq!(fields[0].detail.as_deref(), Some("ip"));
+    assert_eq!(fields[1].range.end, Position::new(7, 1));
+}
+
+#[test]
+fn folding_ranges_cover_blocks_and_comments() {
+    let ranges: Vec<_> = folding_ranges(Lang::Wpl, WPL)
+        .into_iter()
+        .map(|r| (r.start_line, r.end_line, r.kind))
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![
+            (0, 6, None),
+            (1, 2, None),
+            (4, 5, Some(FoldingRangeKind::Comment)),
+        ]
+    );
+}
+
+#[test]
+fn diagnostics_and_formatting_follow_engine_parse() {
+    assert!(analysis::diagnostics(Lang::Wpl, WPL).is_empty());
+
+    let broken = "package demo {\n  rule r { (digit:id }\n";
+    let diagnostics = analysis::diagnostics(Lang::Wpl, broken);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].source.as_deref(),
+        Some(analysis::DIAGNOSTIC_SOURCE)
+    );
+    // 无法解析的文档不做格式化
+    assert!(analysis::formatting(Lang::Wpl, broken).is_none());
+
+    let oml = "name : demo\nrule : demo/rule\n---\nvalue=digit(1);";
+    let edits = analysis::formatting(Lang::Oml, oml).unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(
+        edits[0].new_text,
+        "name : demo\nrule : demo/rule\n---\n\nvalue = digit(1);\n\n"
+    );
+    let formatted = edits[0].new_text.as_str();
+    assert_eq!(analysis::formatting(Lang::Oml, formatted), Some(Vec::new()));
+}
+
+// 结构化诊断的 1 起始行列换算为 LSP 的 0 起始位置
+#[test]
+fn to_lsp_converts_diagnostic_range() {
+    let text = "name : 中文\n---\nvalue = bad ;\n";
+    let diagnostic = Diagnostic::syntax(OML_SYNTAX, text, "parse error at line 3, column 9");
+    let converted = analysis::to_lsp(text, &diagnostic);
+    assert_eq!(
+        converted.range,
+        Range::new(Position::new(2, 8), Position::new(2, 11))
+    );
+    assert_eq!(
+        converted.code,
+        Some(NumberOrString::String(OML_SYNTAX.to_string()))
+    );
+
+    // 没有位置信息时落在首个非空行
+    let converted = analysis::to_lsp(text, &Diagnostic::error(OML_SYNTAX, "expected `---`"));
+    assert_eq!(
+        converted.range,
+        Range::new(Position::new(0, 0), Position::new(0, 9))
+    );
+}
@@ -0,0 +1 @@
+pub mod analysis_test;
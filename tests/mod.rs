@@ -1,2 +1,4 @@
 pub mod api;
+pub mod db;
+pub mod lsp;
 pub mod utils;
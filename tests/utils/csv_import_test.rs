@@ -0,0 +1,72 @@
+use std::io::Write;
+use wp_editor::utils::csv_import::{decode_upload, parse_csv, table_columns};
+
+// 建表 SQL 列提取：跳过约束定义，括号内的逗号不影响切分
+#[test]
+fn table_columns_skip_constraints() {
+    let create_sql = r#"CREATE TABLE IF NOT EXISTS ip_owner (
+    "ip" TEXT NOT NULL,
+    amount DECIMAL(10, 2),
+    owner TEXT,
+    PRIMARY KEY (ip)
+);"#;
+    assert_eq!(table_columns(create_sql), vec!["ip", "amount", "owner"]);
+    assert!(table_columns("DROP TABLE ip_owner").is_empty());
+}
+
+// 建表 SQL 后附带索引语句时，只取建表语句自身的列定义
+#[test]
+fn table_columns_ignore_trailing_index_statements() {
+    let create_sql = r#"CREATE TABLE ip_owner (
+    ip TEXT NOT NULL,
+    note TEXT DEFAULT '(none)',
+    owner TEXT
+);
+CREATE INDEX idx_ip_owner_ip ON ip_owner (ip);
+CREATE UNIQUE INDEX idx_ip_owner_owner ON ip_owner (owner, ip);
+"#;
+    assert_eq!(table_columns(create_sql), vec!["ip", "note", "owner"]);
+}
+
+// CSV 列按建表 SQL 顺序重排，表头大小写与空白不敏感
+#[test]
+fn parse_csv_reorders_columns_by_create_sql() {
+    let columns = vec!["ip".to_string(), "owner".to_string()];
+    let table = parse_csv("Owner, IP\nbob,10.0.0.1\n\"a,b\",10.0.0.2\n", &columns).unwrap();
+
+    assert_eq!(table.columns, columns);
+    assert_eq!(
+        table.rows,
+        vec![vec!["10.0.0.1", "bob"], vec!["10.0.0.2", "a,b"]]
+    );
+    assert_eq!(
+        table.to_csv().unwrap(),
+        "ip,owner\n10.0.0.1,bob\n10.0.0.2,\"a,b\"\n"
+    );
+}
+
+// 列不一致或行长度不一致时返回校验错误
+#[test]
+fn parse_csv_rejects_mismatched_columns() {
+    let columns = vec!["ip".to_string(), "owner".to_string()];
+    assert!(parse_csv("ip,extra\n1,2\n", &columns).is_err());
+    assert!(parse_csv("ip,owner\n10.0.0.1\n", &columns).is_err());
+    assert!(parse_csv("", &[]).is_err());
+}
+
+// gzip 内容自动解压，并去除 UTF-8 BOM
+#[test]
+fn decode_upload_handles_gzip_and_bom() {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all("\u{feff}ip\n10.0.0.1\n".as_bytes())
+        .unwrap();
+    let gz = encoder.finish().unwrap();
+
+    assert_eq!(decode_upload(&gz).unwrap(), "ip\n10.0.0.1\n");
+    assert_eq!(decode_upload(b"ip\n1\n").unwrap(), "ip\n1\n");
+    assert!(decode_upload(&[0xff, 0xfe, 0x00]).is_err());
+
+    let table = parse_csv(&decode_upload(&gz).unwrap(), &[]).unwrap();
+    assert_eq!(table.columns, vec!["ip"]);
+}
@@ -0,0 +1,125 @@
+use actix_web::ResponseError;
+use actix_web::body::to_bytes;
+use actix_web::http::StatusCode;
+use wp_editor::error::AppError;
+use wp_editor::lsp::analysis::{Lang, check};
+use wp_editor::utils::diagnostic::{
+    Diagnostic, OML_SYNTAX, SourcePos, SourceRange, WPL_DATA, WPL_SYNTAX, locate, offset_of,
+};
+use wp_editor::warp_check_record;
+
+const SOURCE: &str = "package demo {\n    rule nginx { (digit:id }\n}\n";
+
+fn pos(line: usize, column: usize) -> SourcePos {
+    SourcePos { line, column }
+}
+
+// 位置解析：行列指向标识符时覆盖整个标识符，只有行号时指向首个非空白字符
+#[test]
+fn locate_reads_line_and_column_from_message() {
+    let range = locate(SOURCE, "parse error at line 2, column 10\n  |\n2 | ...").unwrap();
+    assert_eq!(range.start, pos(2, 10));
+    assert_eq!(range.end, pos(2, 15));
+
+    let range = locate(SOURCE, "语法错误: 第 2 行第 28 列").unwrap();
+    assert_eq!(range.start, pos(2, 28));
+    assert_eq!(range.end, pos(2, 29));
+
+    let range = locate(SOURCE, "unexpected token at line: 2").unwrap();
+    assert_eq!(range.start, pos(2, 5));
+    assert_eq!(range.end, pos(2, 9));
+}
+
+#[test]
+fn locate_ignores_positions_outside_source() {
+    assert_eq!(locate(SOURCE, "expected `{`"), None);
+    assert_eq!(locate(SOURCE, "parse error at line 9, column 1"), None);
+    // 列号超出行尾时落在行尾
+    let range = locate(SOURCE, "line 1, column 99").unwrap();
+    assert_eq!(range.start, pos(1, 15));
+    assert_eq!(range.end, range.start);
+}
+
+#[test]
+fn offset_of_counts_columns_in_chars() {
+    let source = "a\n中文x\n";
+    assert_eq!(offset_of(source, pos(1, 1)), Some(0));
+    assert_eq!(
+        offset_of(source, pos(2, 3)),
+        Some(source.find('x').unwrap())
+    );
+    assert_eq!(offset_of(source, pos(2, 4)), Some(source.len() - 1));
+    assert_eq!(offset_of(source, pos(2, 5)), None);
+    assert_eq!(offset_of(source, pos(4, 1)), None);
+}
+
+// 错误响应沿用原有错误码，details.diagnostics 给出结构化诊断
+#[actix_web::test]
+async fn syntax_error_response_carries_diagnostics() {
+    let err = AppError::wpl_syntax(SOURCE, "parse error at line 2, column 10");
+    let resp = err.error_response();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body = to_bytes(resp.into_body()).await.expect("read body failed");
+    let body: serde_json::Value = serde_json::from_slice(&body).expect("parse body failed");
+    assert_eq!(body["error"]["code"], "WPL_PARSE_ERROR");
+    assert!(
+        body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("WPL 解析失败")
+    );
+
+    let diagnostic = &body["error"]["details"]["diagnostics"][0];
+    assert_eq!(diagnostic["severity"], "error");
+    assert_eq!(diagnostic["code"], WPL_SYNTAX);
+    assert_eq!(
+        diagnostic["range"],
+        serde_json::json!({ "start": { "line": 2, "column": 10 }, "end": { "line": 2, "column": 15 } })
+    );
+    assert!(diagnostic.get("log_offset").is_none());
+}
+
+#[test]
+fn data_error_reports_wpl_data_diagnostic() {
+    let rule = "package demo { rule r { (digit:id, chars:name) } }";
+    let Err(AppError::Diagnostics {
+        code, diagnostics, ..
+    }) = warp_check_record(rule, "not-a-number abc")
+    else {
+        panic!("日志不匹配规则时应返回诊断错误");
+    };
+    assert_eq!(code, "WPL_PARSE_ERROR");
+    assert_eq!(diagnostics[0].code, WPL_DATA);
+    assert_eq!(diagnostics[0].range, None::<SourceRange>);
+}
+
+#[test]
+fn diagnostic_without_position_has_no_range() {
+    let diagnostic = Diagnostic::syntax(WPL_SYNTAX, SOURCE, "expected rule");
+    assert_eq!(diagnostic.range, None);
+    assert_eq!(diagnostic.message, "expected rule");
+    assert_eq!(
+        serde_json::to_value(&diagnostic).unwrap(),
+        serde_json::json!({ "severity": "error", "message": "expected rule", "code": WPL_SYNTAX })
+    );
+}
+
+// 引擎真实的语法错误（非构造文本）也能定位到出错行，且不早于出错的字段列表
+#[test]
+fn engine_syntax_errors_are_located() {
+    let Err(AppError::Diagnostics { diagnostics, .. }) = warp_check_record(SOURCE, "1") else {
+        panic!("WPL 语法错误应返回诊断错误");
+    };
+    assert_eq!(diagnostics[0].code, WPL_SYNTAX);
+    let range = diagnostics[0].range.expect("WPL 语法错误应带源码位置");
+    assert_eq!((range.start.line, range.end.line), (2, 2));
+    assert!(range.start.column >= 18, "{range:?}");
+
+    let oml = "name : demo\nrule : /demo/*\n---\nvalue = take( ;\n";
+    let diagnostic = check(Lang::Oml, oml).unwrap_err();
+    assert_eq!(diagnostic.code, OML_SYNTAX);
+    let range = diagnostic.range.expect("OML 语法错误应带源码位置");
+    assert_eq!((range.start.line, range.end.line), (4, 4));
+    assert!(range.start.column >= 9, "{range:?}");
+}
@@ -0,0 +1,14 @@
+use wp_editor::utils::diff::unified_diff;
+
+// 统一 diff：包含文件头与增删行，内容一致时为空
+#[test]
+fn unified_diff_reports_changed_lines() {
+    let old = "ip,owner\n10.0.0.1,bob\n10.0.0.2,amy\n";
+    let new = "ip,owner\n10.0.0.1,bob\n10.0.0.3,amy\n";
+
+    let diff = unified_diff(old, new, "rev1", "rev2");
+    assert!(diff.starts_with("--- rev1\n+++ rev2\n"), "{diff}");
+    assert!(diff.contains("-10.0.0.2,amy\n"), "{diff}");
+    assert!(diff.contains("+10.0.0.3,amy\n"), "{diff}");
+    assert!(unified_diff(old, old, "rev1", "rev2").is_empty());
+}
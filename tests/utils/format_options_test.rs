@@ -0,0 +1,77 @@
+use std::fs;
+use tempfile::TempDir;
+use wp_editor::utils::format_options::{
+    CONFIG_FILE, FormatCheck, FormatOptions, FormatOverrides, TrailingComma,
+};
+
+// 仓库未提供配置文件时使用默认风格。
+#[test]
+fn load_should_default_without_config() {
+    let dir = TempDir::new().unwrap();
+    assert_eq!(
+        FormatOptions::load(dir.path()).unwrap(),
+        FormatOptions::default()
+    );
+}
+
+// 配置文件中未声明的项保持默认值。
+#[test]
+fn load_should_read_partial_config() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join(CONFIG_FILE),
+        "use_tabs = true\nmax_width = 80\ntrailing_comma = \"always\"\n",
+    )
+    .unwrap();
+
+    let options = FormatOptions::load(dir.path()).unwrap();
+    assert!(options.use_tabs);
+    assert_eq!(options.max_width, 80);
+    assert_eq!(options.trailing_comma, TrailingComma::Always);
+    assert_eq!(options.indent_width, 4);
+    assert_eq!(options.indent_unit(), "\t");
+}
+
+#[test]
+fn load_should_reject_invalid_config() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join(CONFIG_FILE),
+        "trailing_comma = \"sometimes\"\n",
+    )
+    .unwrap();
+    assert!(FormatOptions::load(dir.path()).is_err());
+}
+
+// 请求参数只覆盖显式给出的项。
+#[test]
+fn overrides_should_apply_given_fields() {
+    let base = FormatOptions {
+        indent_width: 2,
+        align_assignments: true,
+        ..FormatOptions::default()
+    };
+    let overrides = FormatOverrides {
+        indent_width: Some(8),
+        ..FormatOverrides::default()
+    };
+
+    let options = overrides.apply(base);
+    assert_eq!(options.indent_width, 8);
+    assert!(options.align_assignments);
+    assert_eq!(options.indent_unit(), " ".repeat(8));
+}
+
+// 已格式化时 diff 为空，否则给出带文件名的统一 diff。
+#[test]
+fn format_check_should_report_diff() {
+    let same = FormatCheck::new("a = 1;\n", "a = 1;\n", "demo.oml");
+    assert!(same.formatted);
+    assert!(same.diff.is_empty());
+
+    let changed = FormatCheck::new("a=1;\n", "a = 1;\n", "demo.oml");
+    assert!(!changed.formatted);
+    assert!(changed.diff.contains("--- a/demo.oml"), "{}", changed.diff);
+    assert!(changed.diff.contains("+++ b/demo.oml"), "{}", changed.diff);
+    assert!(changed.diff.contains("-a=1;\n+a = 1;"), "{}", changed.diff);
+}
@@ -0,0 +1,106 @@
+use chrono::Utc;
+use std::fs;
+use tempfile::TempDir;
+use wp_editor::db::KnowledgeConfig;
+use wp_editor::utils::knowledge::{
+    KNOWDB_DIR, OmlTableRef, materialize_configs, oml_table_refs, paged_sql, validate_table_name,
+};
+
+fn config(file_name: &str, is_active: bool) -> KnowledgeConfig {
+    KnowledgeConfig {
+        id: 0,
+        file_name: file_name.to_string(),
+        config_content: Some(format!("[[tables]]\nname = \"{file_name}\"\n")),
+        create_sql: Some(format!("CREATE TABLE {file_name} (ip TEXT)")),
+        insert_sql: Some(format!("INSERT INTO {file_name} (ip) VALUES (?1)")),
+        data_content: Some("ip\n10.0.0.1\n".to_string()),
+        is_active,
+        updated_at: Utc::now(),
+        created_at: Utc::now(),
+    }
+}
+
+#[test]
+fn materialize_writes_only_active_configs() {
+    let dir = TempDir::new().unwrap();
+    let stale = dir.path().join(KNOWDB_DIR).join("stale");
+    fs::create_dir_all(&stale).unwrap();
+
+    let configs = [
+        config("ip_owner", true),
+        config("disabled", false),
+        config("../escape", true),
+    ];
+    assert_eq!(materialize_configs(dir.path(), &configs).unwrap(), 1);
+
+    let knowdb_dir = dir.path().join(KNOWDB_DIR);
+    assert!(!stale.exists(), "旧的物化目录应被清理");
+    assert!(!knowdb_dir.join("disabled").exists());
+    assert_eq!(
+        fs::read_to_string(knowdb_dir.join("ip_owner/data.csv")).unwrap(),
+        "ip\n10.0.0.1\n"
+    );
+    assert!(knowdb_dir.join("ip_owner/create.sql").exists());
+    assert!(knowdb_dir.join("ip_owner/insert.sql").exists());
+    assert_eq!(
+        fs::read_to_string(knowdb_dir.join("knowdb.toml")).unwrap(),
+        "[[tables]]\nname = \"ip_owner\"\n\n"
+    );
+    assert!(dir.path().join(".run").is_dir());
+}
+
+#[test]
+fn oml_table_refs_scans_repo_recursively() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("nested")).unwrap();
+    fs::write(
+        dir.path().join("nested/zone.oml"),
+        "name : zone_model\n---\nzone = select zone from IP_ZONE where ip = read(sip) ;\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("plain.oml"),
+        "zone = select zone from ip_zone where ip = read(dip) ;\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("notes.txt"), "select a from ip_owner").unwrap();
+
+    let refs = oml_table_refs(dir.path());
+    assert_eq!(refs.keys().collect::<Vec<_>>(), vec!["ip_zone"]);
+    assert_eq!(
+        refs["ip_zone"],
+        vec![
+            OmlTableRef {
+                model: "zone_model".to_string(),
+                path: "nested/zone.oml".to_string(),
+            },
+            OmlTableRef {
+                model: "plain.oml".to_string(),
+                path: "plain.oml".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn paged_sql_wraps_query_with_limit_and_offset() {
+    assert_eq!(
+        paged_sql("SELECT * FROM ip_zone", 3, 20).unwrap(),
+        "SELECT * FROM (SELECT * FROM ip_zone) LIMIT 20 OFFSET 40"
+    );
+}
+
+#[test]
+fn paged_sql_rejects_out_of_range_page() {
+    assert!(paged_sql("SELECT * FROM ip_zone", usize::MAX, 20).is_err());
+    assert!(paged_sql("SELECT * FROM ip_zone", usize::MAX / 20 + 1, 20).is_err());
+    assert!(paged_sql("SELECT * FROM ip_zone", 0, 20).is_err());
+}
+
+#[test]
+fn table_name_rejects_sql_fragments() {
+    assert!(validate_table_name("ip_zone_2").is_ok());
+    assert!(validate_table_name("").is_err());
+    assert!(validate_table_name("1table").is_err());
+    assert!(validate_table_name("t; DROP TABLE x").is_err());
+}
@@ -1,4 +1,11 @@
+pub mod csv_import_test;
+pub mod diagnostic_test;
+pub mod diff_test;
+pub mod format_options_test;
+pub mod knowledge_test;
 pub mod oml_formatter_test;
 pub mod oml_test;
+pub mod perf_test;
+pub mod sql_guard_test;
 pub mod wpl_formatter_test;
 pub mod wpl_test;
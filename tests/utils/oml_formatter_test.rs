@@ -1,4 +1,5 @@
-use wp_editor::OmlFormatter;
+use wp_editor::error::AppError;
+use wp_editor::{FormatOptions, OmlFormatter};
 
 #[test]
 fn format_content_should_keep_blocks_neat() {
@@ -364,3 +365,103 @@ event_risk_level = match read(option:[judgeForTI]) {
         "chars(...) 内部内容应保持原样，不因分号被拆分"
     );
 }
+
+// 相邻单行赋值对齐 `=`，match 分支与空行打断对齐。
+#[test]
+fn format_content_should_align_assignments() {
+    let formatter = OmlFormatter::with_options(FormatOptions {
+        indent_width: 2,
+        align_assignments: true,
+        ..FormatOptions::default()
+    });
+    let raw = "name : demo\n---\nsip = read(sip);\naccess_ip: ip = read(access_ip);\n\nflag = match read(kind) {\nchars(A) => chars(yes);\n}\n";
+
+    let formatted = formatter.format_content(raw);
+    let expected = "\
+name : demo
+---
+
+sip           = read(sip);
+access_ip: ip = read(access_ip);
+
+flag = match read(kind) {
+  chars(A) => chars(yes);
+}
+
+";
+    assert_eq!(formatted, expected);
+    assert_eq!(formatter.format_content(&formatted), formatted);
+}
+
+// 超出行宽的管道表达式拆到续行。
+#[test]
+fn format_content_should_wrap_long_pipe() {
+    let formatter = OmlFormatter::with_options(FormatOptions {
+        max_width: 30,
+        ..FormatOptions::default()
+    });
+    let raw = "data = pipe read(raw) | base64_decode | to_json;\n";
+
+    let formatted = formatter.format_content(raw);
+    let expected = "\
+data = pipe read(raw)
+    | base64_decode
+    | to_json;
+
+";
+    assert_eq!(formatted, expected);
+    assert_eq!(formatter.format_content(&formatted), formatted);
+}
+
+#[test]
+fn format_content_should_keep_multiline_literal_untouched() {
+    let formatter = OmlFormatter::with_options(FormatOptions {
+        max_width: 20,
+        align_assignments: true,
+        ..FormatOptions::default()
+    });
+    let raw = "\
+a = take();
+cc = take();
+banner = \"first | second
+key=value | tail
+
+
+last=1\";
+bb = take();
+";
+
+    let formatted = formatter.format_content(raw);
+    let expected = "\
+a  = take();
+cc = take();
+banner = \"first | second
+key=value | tail
+
+
+last=1\";
+bb = take();
+
+";
+    assert_eq!(formatted, expected);
+    assert_eq!(formatter.format_content(&formatted), formatted);
+}
+
+// format 经引擎校验：合法模型返回排版结果，语法错误返回诊断而不是原文。
+#[test]
+fn format_should_validate_with_engine() {
+    let formatter = OmlFormatter::new();
+    let raw = "name : /oml/example/simple\nrule : /example/simple*\n---\nrecv_time  = take() ;\n";
+    let formatted = formatter.format(raw).expect("合法模型应格式化成功");
+    assert_eq!(
+        formatted,
+        "name : /oml/example/simple\nrule : /example/simple*\n---\n\nrecv_time = take();\n\n"
+    );
+
+    let Err(AppError::Diagnostics { code, .. }) =
+        formatter.format("name : demo\n---\nx = read(;\n")
+    else {
+        panic!("语法错误应返回诊断");
+    };
+    assert_eq!(code, "OML_PARSE_ORION_ERROR");
+}
@@ -1,4 +1,5 @@
 use wp_data_fmt::{DataFormat, FormatType, Json};
+use wp_editor::utils::oml::{model_name, referenced_tables, strip_comments};
 use wp_editor::{convert_record, record_to_fields, warp_check_record};
 
 #[test]
@@ -63,3 +64,144 @@ src_ip     = take(option:[src-ip,sip,source-ip] );
     assert!(!json_string.is_empty(), "JSON 格式化结果不应为空");
     assert!(json_string.contains("src_ip"), "JSON 应包含 src_ip 字段");
 }
+
+// 字符串与 chars(...) 中的 `//`、`/*` 不应被当作注释去除
+#[test]
+fn test_oml_comments_keep_url_literals() {
+    let log_data = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0" "-""#;
+
+    let wpl_rule = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let oml_rule = r#"name : /oml/example/simple
+// 行注释
+rule :
+    /example/simple*
+---
+/* 块注释
+   可跨多行 */
+site   = chars(http://example.com/a) ; // 行尾注释
+src_ip = take(option:[sip]) ; /* 行内块注释 */"#;
+
+    let wpl_record = warp_check_record(wpl_rule, log_data).expect("WPL 解析应该成功");
+    let oml_record = convert_record(oml_rule, wpl_record).expect("OML 转换应该成功");
+    let fields = record_to_fields(&oml_record);
+
+    let value_of = |name: &str| {
+        fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.value.clone())
+            .unwrap_or_else(|| panic!("应该有 {} 字段", name))
+    };
+    assert_eq!(value_of("site"), "http://example.com/a");
+    assert_eq!(value_of("src_ip"), "222.133.52.20");
+}
+
+#[test]
+fn test_oml_referenced_tables() {
+    let oml_rule = r#"name : /oml/example/zone
+rule : /example/simple*
+---
+// owner = select owner from commented_out where ip = read(sip) ;
+zone  = select zone from ip_zone where ip = read(sip) ;
+owner = SELECT owner, dept
+        FROM Ip_Owner where ip = read(sip) ;
+again = select zone from ip_zone where ip = read(dip) ;"#;
+
+    assert_eq!(model_name(oml_rule).as_deref(), Some("/oml/example/zone"));
+    assert_eq!(referenced_tables(oml_rule), vec!["Ip_Owner", "ip_zone"]);
+}
+
+#[test]
+fn test_strip_comments_removes_line_and_block_comments() {
+    let oml = "a = take() ; // trailing\n/* block\ncomment */b = take() ;\n";
+    assert_eq!(strip_comments(oml), "a = take() ; \n \nb = take() ;\n");
+}
+
+#[test]
+fn test_strip_comments_keeps_markers_in_literals_and_raw_funcs() {
+    let oml = concat!(
+        "url = chars(http://example.com/a) ;\n",
+        "s = \"a // b \\\" /* c\" ;\n",
+        "q = 'x // y \\' /* z' ; // gone\n",
+        "r = r#\"x // \"y\" /* z\"# ; // gone\n",
+        "v = xchars(a//b) ;\n",
+    );
+    assert_eq!(
+        strip_comments(oml),
+        concat!(
+            "url = chars(http://example.com/a) ;\n",
+            "s = \"a // b \\\" /* c\" ;\n",
+            "q = 'x // y \\' /* z' ; \n",
+            "r = r#\"x // \"y\" /* z\"# ; \n",
+            "v = xchars(a\n",
+        )
+    );
+}
+
+#[test]
+fn test_strip_comments_drops_unterminated_block_comment() {
+    assert_eq!(
+        strip_comments("a = take() ; /* open\nb"),
+        "a = take() ;  \n"
+    );
+}
+
+// `rule :` 路径中的 `/*` 是通配符，不能当作块注释吞掉后续模型
+#[test]
+fn test_strip_comments_keeps_rule_path_wildcards() {
+    let oml = concat!(
+        "name : /oml/nginx\n",
+        "rule : /nginx/*\n",
+        "    /*\n",
+        "---\n",
+        "a = take() ; /* 注释 */\n",
+        "b = take() ;\n",
+    );
+    assert_eq!(
+        strip_comments(oml),
+        concat!(
+            "name : /oml/nginx\n",
+            "rule : /nginx/*\n",
+            "    /*\n",
+            "---\n",
+            "a = take() ;  \n",
+            "b = take() ;\n",
+        )
+    );
+}
+
+#[test]
+fn test_oml_transform_with_wildcard_rule() {
+    let log_data = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0" "-""#;
+
+    let wpl_rule = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let oml_rule = r#"name : /oml/example/simple
+rule : /example/*
+---
+src_ip = take(option:[sip]) ; /* 行内块注释 */
+site   = chars(example) ;"#;
+
+    let wpl_record = warp_check_record(wpl_rule, log_data).expect("WPL 解析应该成功");
+    let oml_record = convert_record(oml_rule, wpl_record).expect("OML 转换应该成功");
+    let fields = record_to_fields(&oml_record);
+
+    let value_of = |name: &str| {
+        fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.value.clone())
+            .unwrap_or_else(|| panic!("应该有 {} 字段", name))
+    };
+    assert_eq!(value_of("src_ip"), "222.133.52.20");
+    assert_eq!(value_of("site"), "example");
+}
@@ -0,0 +1,59 @@
+use wp_editor::utils::perf::{ALLOC_UNAVAILABLE, BenchConfig, run_benchmark};
+
+const NGINX_LOG: &str = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3770.142 Safari/537.36" "-""#;
+
+const NGINX_WPL: &str = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+fn config(logs: String, oml: Option<&str>, events: usize) -> BenchConfig {
+    BenchConfig {
+        rules: NGINX_WPL.to_string(),
+        oml: oml.map(str::to_string),
+        logs,
+        delimiter: None,
+        events,
+        warmup: 5,
+    }
+}
+
+#[test]
+fn benchmark_cycles_samples_and_reports_latency() {
+    let logs = format!("{NGINX_LOG}\nbroken line\n");
+    let report = run_benchmark(&config(logs, None, 40)).expect("性能测试应该成功");
+
+    assert_eq!(report.samples, 2);
+    assert_eq!(report.events, 40);
+    assert_eq!(report.success, 20, "样本应循环复用");
+    assert_eq!(report.failed, 20);
+    assert!(report.first_error.is_some(), "应记录首个失败原因");
+    assert!(!report.with_oml);
+    assert!(report.latency.p50_us <= report.latency.p99_us);
+    assert!(report.latency.p99_us <= report.latency.max_us);
+    assert!(report.throughput_eps > 0.0);
+    // 测试二进制未注册计数分配器
+    assert!(!report.alloc.tracked);
+    assert_eq!(report.alloc.unavailable, Some(ALLOC_UNAVAILABLE));
+}
+
+#[test]
+fn benchmark_runs_oml_transform() {
+    let oml = r#"name : /oml/example/simple
+rule :
+    /example/simple*
+---
+src_ip = take(option:[sip]) ;"#;
+    let report =
+        run_benchmark(&config(NGINX_LOG.to_string(), Some(oml), 10)).expect("性能测试应该成功");
+
+    assert!(report.with_oml);
+    assert_eq!(report.success, 10);
+}
+
+#[test]
+fn benchmark_rejects_invalid_event_count() {
+    let result = run_benchmark(&config(NGINX_LOG.to_string(), None, 0));
+    assert!(result.is_err(), "事件数为 0 应返回参数错误");
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+use wp_editor::error::AppError;
+use wp_editor::utils::sql_guard::{
+    QueryLimits, QueryResult, StatementKind, check_read_only, run_read_only, run_with_timeout,
+};
+
+fn limits(max_rows: usize) -> QueryLimits {
+    QueryLimits {
+        timeout: Duration::from_secs(5),
+        max_rows,
+    }
+}
+
+// 只读语句：SELECT/WITH/EXPLAIN 放行，注释与末尾分号被去除
+#[test]
+fn read_only_statements_are_allowed() {
+    let query = check_read_only("select ip, owner from ip_zone -- 注释\n;").unwrap();
+    assert_eq!(query.kind, StatementKind::Select);
+    assert_eq!(query.sql, "select ip, owner from ip_zone");
+
+    let query = check_read_only("SELECT /* 列 */ ip FROM ip_zone -- 尾注释").unwrap();
+    assert_eq!(query.sql, "SELECT   ip FROM ip_zone");
+
+    let query = check_read_only("WITH t AS (SELECT 1 AS n) SELECT n FROM t").unwrap();
+    assert_eq!(query.kind, StatementKind::Select);
+
+    let query = check_read_only("EXPLAIN QUERY PLAN SELECT * FROM ip_zone").unwrap();
+    assert_eq!(query.kind, StatementKind::Explain);
+    assert_eq!(query.limited_sql(10), query.sql);
+}
+
+// 执行的 SQL 取自原文：多字符运算符与小数不会被拆开
+#[test]
+fn operators_and_decimals_are_kept_verbatim() {
+    for sql in [
+        "SELECT * FROM ip_zone WHERE score >= 10",
+        "SELECT * FROM ip_zone WHERE score <= 10",
+        "SELECT * FROM ip_zone WHERE owner != 'x'",
+        "SELECT * FROM ip_zone WHERE owner <> 'x'",
+        "SELECT owner || '-' || ip FROM ip_zone",
+        "SELECT * FROM ip_zone WHERE score > 1.5",
+    ] {
+        assert_eq!(check_read_only(sql).unwrap().sql, sql);
+        assert_eq!(check_read_only(&format!("{sql};")).unwrap().sql, sql);
+    }
+}
+
+// 写操作、附加数据库与多语句都被拒绝
+#[test]
+fn write_and_multi_statements_are_forbidden() {
+    for sql in [
+        "DROP TABLE ip_zone",
+        "DELETE FROM ip_zone",
+        "ATTACH DATABASE '/tmp/x.db' AS x",
+        "PRAGMA writable_schema = 1",
+        "SELECT 1; DROP TABLE ip_zone",
+        "WITH t AS (SELECT 1) DELETE FROM ip_zone",
+        "/* SELECT */ UPDATE ip_zone SET owner = 'x'",
+    ] {
+        let err = check_read_only(sql).unwrap_err();
+        assert!(matches!(err, AppError::ForbiddenSql(_)), "{sql}: {err}");
+    }
+}
+
+// 字符串、带引号标识符中的关键字与 replace() 函数不视为写操作
+#[test]
+fn keywords_inside_literals_are_ignored() {
+    for sql in [
+        "SELECT * FROM ip_zone WHERE owner = 'drop; delete'",
+        "SELECT \"update\", [delete] FROM ip_zone",
+        "SELECT replace(owner, 'a', 'b') FROM ip_zone",
+        "SELECT 'it''s; DROP TABLE x' AS s",
+    ] {
+        assert!(check_read_only(sql).is_ok(), "{sql}");
+    }
+    assert!(matches!(
+        check_read_only("SELECT 'unterminated"),
+        Err(AppError::Validation(_))
+    ));
+    assert!(matches!(
+        check_read_only(" ; -- 空语句"),
+        Err(AppError::Validation(_))
+    ));
+}
+
+// 表引用：按标识符匹配，字符串中的同名内容不计入
+#[test]
+fn references_matches_identifiers_only() {
+    let query =
+        check_read_only("SELECT * FROM IP_Zone z JOIN \"ip_owner\" o ON z.ip = o.ip").unwrap();
+    assert!(query.references("ip_zone"));
+    assert!(query.references("ip_owner"));
+
+    let query = check_read_only("SELECT 'ip_zone' AS name FROM ip_owner").unwrap();
+    assert!(!query.references("ip_zone"));
+}
+
+// 行数上限：多取一行判断截断
+#[test]
+fn limited_sql_fetches_one_extra_row() {
+    let query = check_read_only("SELECT * FROM ip_zone").unwrap();
+    assert_eq!(
+        query.limited_sql(100),
+        "SELECT * FROM (SELECT * FROM ip_zone) LIMIT 101"
+    );
+
+    let rows = vec![vec!["1".to_string()], vec!["2".to_string()]];
+    let result = QueryResult::new(vec!["n".to_string()], rows.clone(), 1);
+    assert!(result.truncated);
+    assert_eq!(result.row_count, 1);
+    assert!(!QueryResult::new(vec!["n".to_string()], rows, 2).truncated);
+}
+
+#[tokio::test]
+async fn run_read_only_truncates_and_checks_before_execute() {
+    let result = run_read_only("SELECT n FROM t", limits(2), |sql| {
+        assert_eq!(sql, "SELECT * FROM (SELECT n FROM t) LIMIT 3");
+        let rows = (0..3).map(|n| vec![n.to_string()]).collect();
+        Ok((vec!["n".to_string()], rows))
+    })
+    .await
+    .unwrap();
+    assert!(result.truncated);
+    assert_eq!(result.rows, vec![vec!["0"], vec!["1"]]);
+
+    let err = run_read_only("DROP TABLE t", limits(2), |_| {
+        panic!("被拒绝的语句不应执行")
+    })
+    .await
+    .unwrap_err();
+    assert!(matches!(err, AppError::ForbiddenSql(_)));
+}
+
+#[tokio::test]
+async fn slow_query_times_out() {
+    let err = run_with_timeout(Duration::from_millis(20), || {
+        std::thread::sleep(Duration::from_millis(200));
+        Ok(())
+    })
+    .await
+    .unwrap_err();
+    assert!(matches!(err, AppError::QueryTimeout(20)));
+}
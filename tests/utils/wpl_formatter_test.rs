@@ -1,4 +1,7 @@
-use wp_editor::WplFormatter;
+use wp_editor::error::AppError;
+use wp_editor::utils::TrailingComma;
+use wp_editor::utils::diagnostic::WPL_SYNTAX;
+use wp_editor::{FormatOptions, WplFormatter};
 
 // 基础格式化：注解、规则、字段与管道应保持结构且具备幂等性。
 #[test]
@@ -288,3 +291,155 @@ package /raw/web {
         "双引号后紧跟逗号的场景应按普通字符处理，保持换行与缩进"
     );
 }
+
+// 未登记的函数同样按原样函数处理，内部管道与逗号不被拆分。
+#[test]
+fn format_should_keep_unknown_func_raw() {
+    let formatter = WplFormatter::new();
+    let raw = r#"
+package demo {
+    rule r {
+        (ip:sip | f_ip_not_in([10.0.0.1], [10.0.0.2]), chars | f_new_check(a|b, c))
+    }
+}
+"#;
+
+    let formatted = formatter.format_content(raw);
+    let expected = r#"package demo {
+    rule r {
+        (
+            ip:sip | f_ip_not_in([10.0.0.1], [10.0.0.2]),
+            chars | f_new_check(a|b, c)
+        )
+    }
+}
+"#;
+
+    assert_eq!(formatted, expected, "新函数的参数应原样保留");
+}
+
+// 行注释与块注释应原样保留，行尾注释留在原行。
+#[test]
+fn format_should_keep_comments() {
+    let formatter = WplFormatter::new();
+    let raw = r#"
+// nginx 访问日志
+package demo { // 包注释
+rule r {
+/* 字段说明：
+   sip 为来源地址 */
+(ip:sip, // 来源地址
+2*_,
+chars)
+}
+}
+"#;
+
+    let formatted = formatter.format_content(raw);
+    let expected = r#"// nginx 访问日志
+package demo { // 包注释
+    rule r {
+        /* 字段说明：
+   sip 为来源地址 */
+        (
+            ip:sip, // 来源地址
+            2*_,
+            chars
+        )
+    }
+}
+"#;
+
+    assert_eq!(formatted, expected, "注释应被保留");
+    assert_eq!(formatter.format_content(&formatted), formatted);
+}
+
+// 引擎可解析的规则经 format 排版后仍可解析且保持幂等。
+#[test]
+fn format_should_validate_with_engine() {
+    let formatter = WplFormatter::new();
+    let raw = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let once = formatter.format(raw).expect("合法规则应格式化成功");
+    assert!(
+        once.contains("    rule nginx {\n"),
+        "规则应被缩进：{}",
+        once
+    );
+    assert_eq!(formatter.format(&once).expect("格式化结果应可解析"), once);
+}
+
+// 无法解析的规则应返回语法诊断，而不是原样返回。
+#[test]
+fn format_should_fail_on_syntax_error() {
+    let formatter = WplFormatter::new();
+    let Err(AppError::Diagnostics {
+        code, diagnostics, ..
+    }) = formatter.format("package demo {\n    rule nginx { (digit:id }\n}\n")
+    else {
+        panic!("语法错误应返回诊断");
+    };
+    assert_eq!(code, "WPL_PARSE_ERROR");
+    assert_eq!(diagnostics[0].code, WPL_SYNTAX);
+}
+
+// 制表符缩进与尾随逗号策略。
+#[test]
+fn format_should_follow_indent_and_trailing_comma_options() {
+    let raw = "package demo {\nrule r {\n(ip:sip, chars\\,)\n}\n}\n";
+    let always = WplFormatter::with_options(FormatOptions {
+        use_tabs: true,
+        trailing_comma: TrailingComma::Always,
+        ..FormatOptions::default()
+    });
+    let formatted = always.format_content(raw);
+    assert_eq!(
+        formatted,
+        "package demo {\n\trule r {\n\t\t(\n\t\t\tip:sip,\n\t\t\tchars\\,,\n\t\t)\n\t}\n}\n"
+    );
+    assert_eq!(always.format_content(&formatted), formatted);
+
+    let never = WplFormatter::with_options(FormatOptions {
+        trailing_comma: TrailingComma::Never,
+        ..FormatOptions::default()
+    });
+    assert_eq!(
+        never.format_content(&formatted),
+        "package demo {\n    rule r {\n        (\n            ip:sip,\n            chars\\,\n        )\n    }\n}\n"
+    );
+}
+
+// 超出行宽的管道链在顶层管道处拆行，括号内的管道保持不动。
+#[test]
+fn format_should_wrap_long_pipe_chain() {
+    let formatter = WplFormatter::with_options(FormatOptions {
+        max_width: 40,
+        ..FormatOptions::default()
+    });
+    let raw = r#"
+package demo {
+    rule r {
+        (ip:sip | f_ip_in([10.0.0.1]) | symbol(a|b), chars)
+    }
+}
+"#;
+
+    let formatted = formatter.format_content(raw);
+    let expected = r#"package demo {
+    rule r {
+        (
+            ip:sip
+                | f_ip_in([10.0.0.1])
+                | symbol(a|b),
+            chars
+        )
+    }
+}
+"#;
+    assert_eq!(formatted, expected);
+    assert_eq!(formatter.format_content(&formatted), formatted);
+}
@@ -1,5 +1,10 @@
 use wp_data_fmt::{DataFormat, FormatType, Json};
-use wp_editor::{record_to_fields, warp_check_record};
+use wp_editor::utils::{EventIdMode, ParseOptions, TraceKind, TraceStatus};
+use wp_editor::{
+    record_spans, record_to_fields, warp_check_batch, warp_check_outcome, warp_check_record,
+    warp_rule_matrix, warp_trace,
+};
+use wp_model_core::model::DataType;
 
 #[test]
 fn test_warp_check_nginx_log() {
@@ -71,3 +76,346 @@ rule nginx {
         "格式化的 JSON 应包含 IP 值"
     );
 }
+
+#[test]
+fn test_warp_check_batch_reports_each_line() {
+    let line = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3770.142 Safari/537.36" "-""#;
+    let logs = format!("{line}\r\nnot a nginx line\n\n{line}\n");
+
+    let wpl_rule = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let result = warp_check_batch(wpl_rule, &logs, None, &ParseOptions::default())
+        .expect("批量解析应该成功");
+
+    assert_eq!(result.total, 3, "空行应被跳过");
+    assert_eq!(result.success, 2);
+    assert_eq!(result.failed, 1);
+
+    let failed = &result.items[1];
+    assert!(!failed.success);
+    assert_eq!(failed.line, 2, "失败事件应定位到第 2 行");
+    assert!(failed.error.is_some(), "失败事件应携带错误信息");
+
+    let last = &result.items[2];
+    assert!(last.success);
+    assert_eq!(last.line, 4);
+    let fields = record_to_fields(last.record.as_ref().expect("应返回解析结果"));
+    assert!(
+        fields
+            .iter()
+            .any(|f| f.name == "sip" && f.value == "222.133.52.20")
+    );
+    let sip = last
+        .spans
+        .iter()
+        .find(|s| s.name == "sip")
+        .expect("批量结果应带字段位置");
+    assert_eq!((sip.start, sip.end), (0, 13), "位置相对于单条事件");
+}
+
+#[test]
+fn test_warp_rule_matrix_reports_shadowed_rules() {
+    let log_data = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3770.142 Safari/537.36" "-""#;
+
+    let wpl_rule = r#"package /example/matrix {
+rule broken {
+    (ip:sip,digit:bad)
+}
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+rule nginx_copy {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let matrix = warp_rule_matrix(wpl_rule, log_data).expect("矩阵诊断应该成功");
+
+    assert_eq!(matrix.rules.len(), 3, "应运行包内全部规则");
+    assert_eq!(matrix.first_match.as_deref(), Some("nginx"));
+    assert!(matrix.ambiguous, "两条规则同时匹配应标记为歧义");
+
+    let broken = &matrix.rules[0];
+    assert!(!broken.matched);
+    assert!(broken.error.is_some());
+
+    let nginx = &matrix.rules[1];
+    assert!(nginx.matched && !nginx.shadowed);
+    assert!(nginx.field_count >= 7);
+    assert_eq!(nginx.depth, Some(log_data.len()), "匹配成功应消费整行日志");
+
+    let copy = &matrix.rules[2];
+    assert!(copy.matched && copy.shadowed, "后序匹配规则应被标记为遮蔽");
+    assert_eq!(copy.field_count, nginx.field_count);
+}
+
+#[test]
+fn test_record_spans_locate_fields_in_log() {
+    let log_data = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3770.142 Safari/537.36" "-""#;
+
+    let wpl_rule = r#"package /example/simple {
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let record = warp_check_record(wpl_rule, log_data).expect("解析应该成功");
+    let spans = record_spans(log_data, &record);
+
+    let span_of = |name: &str| {
+        spans
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap_or_else(|| panic!("应定位到 {} 字段", name))
+    };
+
+    let sip = span_of("sip");
+    assert_eq!((sip.start, sip.end), (0, 13));
+
+    let request = span_of("http/request");
+    assert_eq!(
+        &log_data[request.byte_start..request.byte_end],
+        "GET /nginx-logo.png HTTP/1.1"
+    );
+
+    let status = span_of("http/status");
+    assert!(status.start > request.end, "字段位置应按顺序递增");
+    assert!(
+        spans.iter().all(|s| s.name != "wp_event_id"),
+        "事件 ID 不来自原始日志"
+    );
+    assert!(
+        spans.iter().all(|s| s.name != "recv_time"),
+        "被规范化的时间值在日志中找不到，不应给出位置"
+    );
+    assert!(spans.iter().all(|s| s.approximate));
+}
+
+#[test]
+fn test_record_spans_skip_ambiguous_values() {
+    let wpl_rule = r#"package /example/dup {
+rule dup {
+    (digit:a,digit:b,digit:c)
+}
+}"#;
+    let log_data = "7 8 7";
+    let record = warp_check_record(wpl_rule, log_data).expect("解析应该成功");
+    let spans = record_spans(log_data, &record);
+    let located: Vec<_> = spans
+        .iter()
+        .map(|s| (s.name.as_str(), s.start, s.end))
+        .collect();
+    // a 的值在日志中出现两次，无法判断来源；c 在 b 之后只剩一处
+    assert_eq!(located, vec![("b", 2, 3), ("c", 4, 5)]);
+
+    // 被忽略的前序片段与字段值相同时，不能把字段定位到被忽略的位置
+    let wpl_rule = r#"package /example/dup {
+rule skip {
+    (2*_,digit:x)
+}
+}"#;
+    let log_data = "1 2 1";
+    let record = warp_check_record(wpl_rule, log_data).expect("解析应该成功");
+    assert!(
+        record_spans(log_data, &record)
+            .iter()
+            .all(|s| s.name != "x"),
+        "字段值出现多次时不应给出位置"
+    );
+}
+
+#[test]
+fn test_warp_trace_records_backtracking_and_fields() {
+    let log_data = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3770.142 Safari/537.36" "-""#;
+
+    let wpl_rule = r#"package /example/trace {
+rule broken {
+    (ip:sip,digit:bad)
+}
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let trace = warp_trace(wpl_rule, log_data).expect("跟踪应该成功");
+
+    assert!(trace.success);
+    assert_eq!(trace.matched_rule.as_deref(), Some("nginx"));
+
+    let first = &trace.steps[0];
+    assert_eq!(first.step, 1);
+    assert_eq!(first.kind, TraceKind::Rule);
+    assert_eq!(first.rule, "broken");
+    assert_eq!(first.status, TraceStatus::Backtracked);
+    assert!(first.message.is_some(), "回退步骤应给出失败原因");
+
+    let matched = &trace.steps[1];
+    assert_eq!(matched.rule, "nginx");
+    assert_eq!(matched.status, TraceStatus::Matched);
+    assert_eq!(matched.end, Some(log_data.len()));
+    assert!(!matched.approximate);
+
+    let sip = trace
+        .steps
+        .iter()
+        .find(|s| s.kind == TraceKind::Field && s.name == "sip")
+        .expect("应包含 sip 字段步骤");
+    assert_eq!(sip.consumed.as_deref(), Some("222.133.52.20"));
+    assert!(sip.approximate, "字段位置为近似值");
+}
+
+#[test]
+fn test_warp_trace_uses_byte_offsets_for_non_ascii_log() {
+    let log_data = "用户 登录 10.0.0.1";
+
+    let wpl_rule = r#"package /example/trace_utf8 {
+rule broken {
+    (chars:user,chars:action,digit:bad)
+}
+rule login {
+    (chars:user,chars:action,ip:sip)
+}
+}"#;
+
+    let trace = warp_trace(wpl_rule, log_data).expect("跟踪应该成功");
+    assert_eq!(trace.matched_rule.as_deref(), Some("login"));
+
+    // 每个带位置的步骤，位置都应能按字节截取出与 consumed 相同的片段
+    for step in &trace.steps {
+        if let (Some(start), Some(end), Some(consumed)) = (step.start, step.end, &step.consumed) {
+            assert_eq!(
+                log_data.get(start..end),
+                Some(consumed.as_str()),
+                "步骤 {} 的位置应为字节偏移",
+                step.step
+            );
+        }
+    }
+
+    let matched = trace
+        .steps
+        .iter()
+        .find(|s| s.kind == TraceKind::Rule && s.rule == "login")
+        .expect("应包含命中规则步骤");
+    assert_eq!(matched.end, Some(log_data.len()));
+
+    let sip = trace
+        .steps
+        .iter()
+        .find(|s| s.kind == TraceKind::Field && s.name == "sip")
+        .expect("应包含 sip 字段步骤");
+    let byte_start = log_data.find("10.0.0.1").unwrap();
+    assert_eq!(sip.start, Some(byte_start));
+    assert_eq!(sip.end, Some(log_data.len()));
+}
+
+#[test]
+fn test_warp_check_outcome_applies_annotations() {
+    let log_data = r#"222.133.52.20 - - [06/Aug/2019:12:12:19 +0800] "GET /nginx-logo.png HTTP/1.1" 200 368 "http://119.122.1.4/" "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3770.142 Safari/537.36" "-""#;
+
+    let wpl_rule = r#"package /example/annotated {
+#[copy_raw(name:"raw_msg")]
+rule nginx {
+    (ip:sip,2*_,time:recv_time<[,]>,http/request",http/status,digit,chars",http/agent",_")
+}
+}"#;
+
+    let outcome =
+        warp_check_outcome(wpl_rule, log_data, &ParseOptions::default()).expect("解析应该成功");
+    assert_eq!(outcome.rule, "nginx");
+
+    let annotation = outcome
+        .annotation
+        .expect("声明注解的规则应返回注解执行结果");
+    assert!(annotation.applied >= 1);
+    assert!(
+        annotation.added.iter().any(|name| name == "raw_msg"),
+        "copy_raw 应新增 raw_msg 字段: {:?}",
+        annotation.added
+    );
+    assert!(annotation.after.len() > annotation.before.len());
+
+    let fields = record_to_fields(&outcome.record);
+    let raw_msg = fields
+        .iter()
+        .find(|f| f.name == "raw_msg")
+        .expect("最终记录应包含 raw_msg");
+    assert_eq!(raw_msg.value, log_data);
+}
+
+#[test]
+fn test_warp_check_outcome_without_annotations() {
+    let wpl_rule = r#"package /example/plain {
+rule ip_only {
+    (ip:sip)
+}
+}"#;
+
+    let outcome =
+        warp_check_outcome(wpl_rule, "10.0.0.1", &ParseOptions::default()).expect("解析应该成功");
+    assert!(outcome.annotation.is_none(), "未声明注解时不返回注解结果");
+}
+
+#[test]
+fn test_parse_options_make_output_deterministic() {
+    let wpl_rule = r#"package /example/options {
+rule with_ignore {
+    (ip:sip,_,digit:port)
+}
+}"#;
+    let logs = "10.0.0.1 x 80\n10.0.0.2 y 443";
+
+    let options = ParseOptions {
+        event_id: EventIdMode::Fixed(100),
+        keep_ignore: true,
+        append_tags: false,
+    };
+    let first = warp_check_batch(wpl_rule, logs, None, &options).expect("批量解析应该成功");
+    let second = warp_check_batch(wpl_rule, logs, None, &options).expect("批量解析应该成功");
+
+    let event_ids = |result: &wp_editor::utils::BatchParseResult| -> Vec<String> {
+        result
+            .items
+            .iter()
+            .map(|item| {
+                record_to_fields(item.record.as_ref().expect("应返回解析结果"))
+                    .into_iter()
+                    .find(|f| f.name == "wp_event_id")
+                    .map(|f| f.value)
+                    .unwrap_or_default()
+            })
+            .collect()
+    };
+    assert_eq!(
+        event_ids(&first),
+        vec!["100", "101"],
+        "固定事件 ID 应按序号递增"
+    );
+    assert_eq!(event_ids(&first), event_ids(&second), "多次运行结果应一致");
+
+    let record = first.items[0].record.as_ref().expect("应返回解析结果");
+    assert!(
+        record.items.iter().any(|f| f.meta == DataType::Ignore),
+        "keep_ignore 时应保留忽略字段"
+    );
+
+    let omit = ParseOptions {
+        event_id: EventIdMode::Omit,
+        ..ParseOptions::default()
+    };
+    let outcome = warp_check_outcome(wpl_rule, "10.0.0.1 x 80", &omit).expect("解析应该成功");
+    let fields = record_to_fields(&outcome.record);
+    assert!(fields.iter().all(|f| f.name != "wp_event_id"));
+    assert!(
+        outcome
+            .record
+            .items
+            .iter()
+            .all(|f| f.meta != DataType::Ignore),
+        "默认应丢弃忽略字段"
+    );
+}